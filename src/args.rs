@@ -56,7 +56,10 @@ pub enum Commands {
     },
     #[command(alias = "r", alias = "start", about = "Start jobs")]
     Run {
-        #[arg(help = "Name of the job to run", exclusive = true)]
+        #[arg(
+            help = "Name of the job to run",
+            conflicts_with_all = ["all", "group", "job"]
+        )]
         name: Option<String>,
         #[arg(
             short,
@@ -86,6 +89,298 @@ pub enum Commands {
         job: Vec<String>,
         #[arg(alias = "except", short, long, help = "Exclude specific job(s)", num_args = 1.., use_value_delimiter = true)]
         exclude: Vec<String>,
+        #[arg(
+            long,
+            short = 'F',
+            help = "Run jobs in this terminal even if a daemon is running"
+        )]
+        foreground: bool,
+    },
+    #[cfg(unix)]
+    #[command(about = "Stop jobs running under the daemon")]
+    Stop {
+        #[arg(help = "Name of the job to stop", exclusive = true)]
+        name: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "Stop all jobs",
+            conflicts_with = "group",
+            conflicts_with = "job"
+        )]
+        all: bool,
+        #[arg(
+            short,
+            long,
+            help = "Stop jobs from specific group(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        group: Vec<String>,
+        #[arg(
+            short,
+            long,
+            help = "Stop specific job(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        job: Vec<String>,
+        #[arg(alias = "except", short, long, help = "Exclude specific job(s)", num_args = 1.., use_value_delimiter = true)]
+        exclude: Vec<String>,
+    },
+    #[cfg(unix)]
+    #[command(about = "Restart jobs running under the daemon")]
+    Restart {
+        #[arg(help = "Name of the job to restart", exclusive = true)]
+        name: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "Restart all jobs",
+            conflicts_with = "group",
+            conflicts_with = "job"
+        )]
+        all: bool,
+        #[arg(
+            short,
+            long,
+            help = "Restart jobs from specific group(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        group: Vec<String>,
+        #[arg(
+            short,
+            long,
+            help = "Restart specific job(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        job: Vec<String>,
+        #[arg(alias = "except", short, long, help = "Exclude specific job(s)", num_args = 1.., use_value_delimiter = true)]
+        exclude: Vec<String>,
+    },
+    #[cfg(unix)]
+    #[command(alias = "st", about = "Show live state of jobs under the daemon")]
+    Status {
+        #[arg(help = "Name of the job to show", exclusive = true)]
+        name: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "Show all jobs",
+            conflicts_with = "group",
+            conflicts_with = "job"
+        )]
+        all: bool,
+        #[arg(
+            short,
+            long,
+            help = "Show jobs from specific group(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        group: Vec<String>,
+        #[arg(
+            short,
+            long,
+            help = "Show specific job(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        job: Vec<String>,
+        #[arg(alias = "except", short, long, help = "Exclude specific job(s)", num_args = 1.., use_value_delimiter = true)]
+        exclude: Vec<String>,
+    },
+    #[cfg(unix)]
+    #[command(about = "Manage the background supervisor daemon")]
+    Daemon {
+        #[command(subcommand)]
+        command: DaemonCommands,
+    },
+    #[command(about = "Export jobs to a bundle file")]
+    Export {
+        #[arg(
+            help = "Name of the job to export",
+            conflicts_with_all = ["all", "group", "job"]
+        )]
+        name: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "Export all jobs",
+            conflicts_with = "group",
+            conflicts_with = "job"
+        )]
+        all: bool,
+        #[arg(
+            short,
+            long,
+            help = "Export jobs from specific group(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        group: Vec<String>,
+        #[arg(
+            short,
+            long,
+            help = "Export specific job(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        job: Vec<String>,
+        #[arg(alias = "except", short, long, help = "Exclude specific job(s)", num_args = 1.., use_value_delimiter = true)]
+        exclude: Vec<String>,
+        #[arg(
+            long,
+            short,
+            help = "Write to a file instead of stdout. With --systemd, a directory such as ~/.config/systemd/user."
+        )]
+        output: Option<std::path::PathBuf>,
+        #[arg(
+            long,
+            help = "Format of the bundle. Defaults to the extension of the output file, or json."
+        )]
+        format: Option<crate::job::format::Format>,
+        #[arg(
+            long,
+            help = "Store working directories below this directory relative to it"
+        )]
+        relative_to: Option<std::path::PathBuf>,
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "relative_to"],
+            help = "Generate systemd user services instead of a bundle"
+        )]
+        systemd: bool,
+        #[arg(
+            long,
+            requires = "systemd",
+            help = "Also generate a systemd target per group that starts its jobs"
+        )]
+        targets: bool,
+    },
+    #[command(about = "Import jobs from a bundle file or another tool's configuration")]
+    Import {
+        #[arg(help = "File to import, or - to read a bundle from stdin")]
+        file: std::path::PathBuf,
+        #[arg(
+            long,
+            help = "Format of the bundle. Defaults to the extension of the file, or json."
+        )]
+        format: Option<crate::job::format::Format>,
+        #[arg(
+            long,
+            group = "source",
+            conflicts_with = "format",
+            help = "Import the processes of a Procfile"
+        )]
+        procfile: bool,
+        #[arg(
+            long,
+            group = "source",
+            conflicts_with = "format",
+            help = "Import the services of a docker-compose file that define a command"
+        )]
+        compose: bool,
+        #[arg(
+            long,
+            group = "source",
+            conflicts_with = "format",
+            help = "Import the programs of a supervisord configuration file"
+        )]
+        supervisord: bool,
+        #[arg(
+            long,
+            group = "source",
+            conflicts_with = "format",
+            help = "Import the apps of a pm2 ecosystem file in JSON"
+        )]
+        pm2: bool,
+        #[arg(long, short = 'w', help = "Overwrite existing jobs with the same name")]
+        overwrite: bool,
+        #[arg(long, help = "Prefix the names of imported jobs, e.g. team-")]
+        rename_prefix: Option<String>,
+        #[arg(long, short, help = "Put imported jobs into this group")]
+        group: Option<String>,
+        #[arg(
+            long,
+            help = "Resolve relative working directories against this directory. Defaults to the current directory."
+        )]
+        base: Option<std::path::PathBuf>,
+    },
+    #[command(about = "Show logs of jobs")]
+    Logs {
+        #[arg(
+            help = "Name of the job to show logs of",
+            conflicts_with_all = ["all", "group", "job"]
+        )]
+        name: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "Show logs of all jobs",
+            conflicts_with = "group",
+            conflicts_with = "job"
+        )]
+        all: bool,
+        #[arg(
+            short,
+            long,
+            help = "Show logs of jobs from specific group(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        group: Vec<String>,
+        #[arg(
+            short,
+            long,
+            help = "Show logs of specific job(s)",
+            num_args = 1..,
+            conflicts_with = "all",
+            use_value_delimiter = true
+        )]
+        job: Vec<String>,
+        #[arg(alias = "except", short, long, help = "Exclude specific job(s)", num_args = 1.., use_value_delimiter = true)]
+        exclude: Vec<String>,
+        #[arg(long, short, help = "Keep printing new output as it is logged")]
+        follow: bool,
+        #[arg(
+            long,
+            short = 'n',
+            help = "Number of lines to show from the end of each log"
+        )]
+        tail: Option<usize>,
+        #[arg(
+            long,
+            value_parser = crate::logs::parse_time,
+            help = "Show output logged after a time, e.g. 2024-01-31 12:00:00 or 10m"
+        )]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        #[arg(
+            long,
+            value_parser = crate::logs::parse_time,
+            help = "Show output logged before a time, e.g. 2024-01-31 12:00:00 or 10m"
+        )]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        #[arg(
+            long,
+            short,
+            help = "Show output of a single stream",
+            default_value = "any"
+        )]
+        stream: crate::job::event::Stream,
+        #[arg(long, short, help = "Show timestamps")]
+        timestamps: bool,
     },
     #[command(alias = "c", alias = "new", about = "Create a job")]
     Create {
@@ -102,8 +397,28 @@ pub enum Commands {
         restart: RestartBehavior,
         #[arg(long, default_value = "exponential-backoff", help = "Restart strategy")]
         restart_strategy: RestartStrategy,
+        #[arg(long, help = "Stop restarting after this many restarts in a row")]
+        max_restarts: Option<u64>,
+        #[arg(
+            long,
+            default_value = "SIGTERM",
+            ignore_case = true,
+            help = "Signal asking the process to stop"
+        )]
+        stop_signal: crate::job::signal::Signal,
+        #[arg(
+            long,
+            default_value_t = crate::job::default_stop_timeout_seconds(),
+            help = "Seconds to wait after the stop signal before killing the process"
+        )]
+        stop_timeout: u64,
         #[arg(long, short = 'w', help = "Overwrite existing job with the same name")]
         overwrite: bool,
+        #[arg(
+            long,
+            help = "File format of the job definition. Defaults to job_format in ~/.tend/config.toml, or json."
+        )]
+        format: Option<crate::job::format::Format>,
         #[arg(
             long,
             short = 'g',
@@ -113,6 +428,34 @@ pub enum Commands {
         group: String,
         #[arg(long, short = 't', help = "Template to use for job configuration")]
         template: Option<crate::job::template::Template>,
+        #[arg(
+            long,
+            short = 'd',
+            help = "Start the job only after this job has started. Repeat or separate jobs with commas.",
+            action = clap::ArgAction::Append,
+            use_value_delimiter = true
+        )]
+        depends_on: Vec<String>,
+        #[arg(
+            long = "env",
+            short = 'e',
+            value_parser = crate::job::env::parse_env_var,
+            help = "Set an environment variable, e.g. -e PORT=8080"
+        )]
+        env: Vec<(String, String)>,
+        #[arg(long, help = "Load environment variables from a dotenv file")]
+        env_file: Option<std::path::PathBuf>,
+        #[arg(long, help = "Keep a persistent log of the job's output")]
+        log: bool,
+        #[arg(
+            long,
+            requires = "log",
+            value_parser = crate::job::log::parse_size,
+            help = "Rotate the log after it reaches this size, e.g. 10M"
+        )]
+        log_max_size: Option<u64>,
+        #[arg(long, requires = "log", help = "Number of rotated log files to keep")]
+        log_max_files: Option<usize>,
         #[arg(help = "Use -- to separate program arguments from job arguments.")]
         args: Vec<String>,
     },
@@ -186,8 +529,13 @@ pub enum Commands {
     Edit {
         #[arg(help = "Name of the job to edit")]
         name: String,
+        #[arg(
+            long,
+            help = "Open the job definition in $VISUAL or $EDITOR instead of using a subcommand"
+        )]
+        editor: bool,
         #[command(subcommand)]
-        command: EditJobCommands,
+        command: Option<EditJobCommands>,
     },
     #[command(alias = "d", alias = "rm", about = "Delete jobs")]
     #[clap(group(clap::ArgGroup::new("input").required(true).args(&["name", "group", "job", "all"])))]
@@ -227,8 +575,75 @@ pub enum Commands {
     },
 }
 
+#[cfg(unix)]
+#[derive(Clone, Debug, Subcommand)]
+pub enum DaemonCommands {
+    #[command(about = "Start the daemon in the background")]
+    Start {
+        #[arg(long, short = 'F', help = "Run the daemon in this terminal")]
+        foreground: bool,
+    },
+    #[command(about = "Stop the daemon and all of its jobs")]
+    Stop,
+}
+
 #[derive(Clone, Debug, Subcommand)]
 pub enum EditJobCommands {
+    #[command(about = "Rename a job")]
+    Rename {
+        #[arg(help = "New name of the job. Must be unique.")]
+        new_name: String,
+    },
+    #[command(about = "Change the program a job runs")]
+    Program {
+        #[arg(help = "Program to run. Must be in PATH or otherwise accessible.")]
+        program: String,
+    },
+    #[command(about = "Change the arguments passed to the program")]
+    Args {
+        #[arg(help = "Use -- to separate program arguments. Leave empty to remove all arguments.")]
+        args: Vec<String>,
+    },
+    #[command(alias = "cwd", about = "Change the working directory of a job")]
+    WorkingDirectory {
+        #[arg(help = "New working directory. Defaults to the current directory.")]
+        path: Option<std::path::PathBuf>,
+    },
+    #[command(about = "Change when a job is restarted")]
+    Restart {
+        #[arg(help = "Restart condition")]
+        restart: RestartBehavior,
+    },
+    #[command(about = "Change how quickly a job is restarted")]
+    RestartStrategy {
+        #[arg(help = "Restart strategy")]
+        restart_strategy: RestartStrategy,
+    },
+    #[command(about = "Limit how many times in a row a job is restarted")]
+    MaxRestarts {
+        #[arg(help = "Maximum number of restarts in a row. Leave empty to restart without limit.")]
+        count: Option<u64>,
+    },
+    #[command(about = "Change how a job's process is asked to stop")]
+    Stop {
+        #[arg(ignore_case = true, help = "Signal asking the process to stop")]
+        signal: crate::job::signal::Signal,
+        #[arg(
+            long,
+            help = "Seconds to wait after the signal before killing the process. Unchanged if not given."
+        )]
+        timeout: Option<u64>,
+    },
+    #[command(about = "Change the template of a job")]
+    Template {
+        #[arg(help = "Template to use for job configuration. Leave empty to remove the template.")]
+        template: Option<crate::job::template::Template>,
+    },
+    #[command(about = "Convert the job definition to another file format")]
+    Format {
+        #[arg(help = "New file format")]
+        format: crate::job::format::Format,
+    },
     #[command(about = "Change the group of a job")]
     Group {
         #[arg(help = "New group name")]
@@ -238,6 +653,159 @@ pub enum EditJobCommands {
         #[command(subcommand)]
         command: EditJobHookCommands,
     },
+    #[command(about = "Change the jobs a job depends on")]
+    DependsOn {
+        #[arg(help = "Names of the jobs to depend on. Leave empty to remove all dependencies.")]
+        jobs: Vec<String>,
+    },
+    #[command(about = "Configure when a running job is considered ready")]
+    Readiness {
+        #[arg(long, help = "Seconds between attempts of TCP and exec probes")]
+        interval: Option<u64>,
+        #[arg(
+            long,
+            help = "Seconds after which an attempt of a TCP or exec probe fails"
+        )]
+        timeout: Option<u64>,
+        #[command(subcommand)]
+        command: Option<EditJobReadinessCommands>,
+    },
+    #[command(about = "Configure periodic checks that restart a job when it stops responding")]
+    Liveness {
+        #[arg(
+            long,
+            help = "Seconds between checks",
+            default_value_t = crate::job::probe::default_liveness_interval_seconds()
+        )]
+        interval: u64,
+        #[arg(long, help = "Seconds after which a check fails", default_value_t = 5)]
+        timeout: u64,
+        #[arg(
+            long,
+            help = "Number of failed checks in a row after which the job is restarted",
+            default_value_t = crate::job::probe::default_failure_threshold()
+        )]
+        failure_threshold: u32,
+        #[command(subcommand)]
+        command: EditJobLivenessCommands,
+    },
+    #[command(about = "Configure the environment of a job")]
+    Env {
+        #[command(subcommand)]
+        command: EditJobEnvCommands,
+    },
+    #[command(about = "Configure the persistent log of a job")]
+    Log {
+        #[command(subcommand)]
+        command: EditJobLogCommands,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum EditJobReadinessCommands {
+    #[command(about = "List readiness probes")]
+    List,
+    #[command(about = "Ready once the job prints a line containing a substring")]
+    LogLine {
+        substring: String,
+        #[arg(
+            long,
+            short,
+            help = "Stream to detect substring in.",
+            default_value = "any"
+        )]
+        stream: crate::job::event::Stream,
+    },
+    #[command(about = "Ready once a TCP connection to a localhost port succeeds")]
+    Tcp { port: u16 },
+    #[command(about = "Ready once a command exits successfully")]
+    Exec {
+        program: String,
+        #[arg(help = "Use -- to separate program arguments from job arguments.")]
+        args: Vec<String>,
+    },
+    #[command(about = "Remove all readiness probes")]
+    Clear,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum EditJobLivenessCommands {
+    #[command(about = "List liveness checks")]
+    List,
+    #[command(about = "Check that a TCP connection to a localhost port succeeds")]
+    Tcp { port: u16 },
+    #[command(about = "Check that an HTTP GET to a localhost URL returns a 2xx or 3xx status")]
+    Http {
+        #[arg(value_parser = crate::job::probe::parse_local_url)]
+        url: String,
+    },
+    #[command(about = "Check that a command exits successfully")]
+    Exec {
+        program: String,
+        #[arg(help = "Use -- to separate program arguments from job arguments.")]
+        args: Vec<String>,
+    },
+    #[command(about = "Remove all liveness checks")]
+    Clear,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum EditJobEnvCommands {
+    #[command(about = "List environment settings")]
+    List,
+    #[command(about = "Set environment variables")]
+    Set {
+        #[arg(
+            required = true,
+            value_parser = crate::job::env::parse_env_var,
+            help = "Variables to set, e.g. PORT=8080"
+        )]
+        vars: Vec<(String, String)>,
+    },
+    #[command(about = "Remove environment variables")]
+    Unset {
+        #[arg(required = true)]
+        keys: Vec<String>,
+    },
+    #[command(about = "Load environment variables from a dotenv file")]
+    File {
+        #[arg(
+            help = "Path to the file, relative to the working directory. Omit to stop using a file."
+        )]
+        path: Option<std::path::PathBuf>,
+    },
+    #[command(about = "Do not inherit the environment of tend, except for allowed variables")]
+    Clear {
+        #[arg(
+            long,
+            short,
+            help = "Variables to keep inheriting, e.g. PATH,HOME",
+            num_args = 1..,
+            use_value_delimiter = true
+        )]
+        allow: Vec<String>,
+    },
+    #[command(about = "Inherit the environment of tend again")]
+    Inherit,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum EditJobLogCommands {
+    #[command(about = "Keep a persistent log of the job's output")]
+    Enable {
+        #[arg(
+            long,
+            value_parser = crate::job::log::parse_size,
+            help = "Rotate the log after it reaches this size, e.g. 10M"
+        )]
+        max_size: Option<u64>,
+        #[arg(long, help = "Number of rotated log files to keep")]
+        max_files: Option<usize>,
+        #[arg(long, help = "Do not compress rotated log files")]
+        no_compress: bool,
+    },
+    #[command(about = "Stop keeping a persistent log")]
+    Disable,
 }
 
 #[derive(Clone, Debug, Subcommand)]
@@ -246,6 +814,16 @@ pub enum EditJobHookCommands {
     Create {
         #[arg(help = "Name of the hook to create")]
         hook: String,
+        #[arg(long, help = "Fire only once the event happened this many times")]
+        count: Option<usize>,
+        #[arg(
+            long,
+            requires = "count",
+            help = "Count only events within this many seconds"
+        )]
+        within: Option<u64>,
+        #[arg(long, help = "Do not fire again for this many seconds after firing")]
+        cooldown: Option<u64>,
         #[command(subcommand)]
         t: JobHook,
     },
@@ -255,12 +833,74 @@ pub enum EditJobHookCommands {
     },
 }
 
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum HookActionKind {
+    Restart,
+    Stop,
+    Exec,
+    RestartJob,
+    StopJob,
+    StartJob,
+    RestartGroup,
+    StopGroup,
+    StartGroup,
+    Signal,
+}
+
+#[derive(Clone, Debug, clap::Args)]
+pub struct HookAction {
+    #[arg(help = "Action to take when the hook fires")]
+    pub action: HookActionKind,
+    #[arg(
+        long,
+        help = "Seconds after which the command of an exec action is killed",
+        default_value_t = crate::job::event::default_exec_timeout_seconds()
+    )]
+    pub timeout: u64,
+    #[arg(
+        long,
+        help = "Job or group acted on by the restart-job, stop-job, start-job, restart-group, stop-group and start-group actions"
+    )]
+    pub target: Option<String>,
+    #[arg(long, ignore_case = true, help = "Signal sent by the signal action")]
+    pub send: Option<crate::job::signal::Signal>,
+    #[arg(last = true, help = "Command to run for an exec action")]
+    pub command: Vec<String>,
+}
+
 #[derive(Clone, Debug, Subcommand)]
 pub enum JobHook {
+    #[command(about = "Act when the process exits successfully")]
+    FinishedSuccess {
+        #[command(flatten)]
+        action: HookAction,
+    },
+    #[command(about = "Act when the process exits unsuccessfully")]
+    FinishedFailure {
+        #[command(flatten)]
+        action: HookAction,
+    },
+    #[command(about = "Act when the process exits with specific codes or signals")]
+    Exit {
+        #[command(flatten)]
+        action: HookAction,
+        #[arg(
+            long = "code",
+            help = "Exit code to match. Can be repeated.",
+            allow_negative_numbers = true
+        )]
+        codes: Vec<i32>,
+        #[arg(
+            long = "signal",
+            ignore_case = true,
+            help = "Signal killing the process to match, e.g. SIGKILL. Can be repeated."
+        )]
+        signals: Vec<crate::job::signal::Signal>,
+    },
     DetectSubstring {
         substring: String,
-        #[arg(help = "Action to take when substring is detected.")]
-        action: crate::job::event::Action,
+        #[command(flatten)]
+        action: HookAction,
         #[arg(
             long,
             short,
@@ -269,4 +909,29 @@ pub enum JobHook {
         )]
         stream: crate::job::event::Stream,
     },
+    DetectRegex {
+        #[arg(help = "Regular expression to match lines against, e.g. \"lost connection|EOF\"")]
+        pattern: crate::job::event::Pattern,
+        #[command(flatten)]
+        action: HookAction,
+        #[arg(long, short, help = "Stream to match lines of.", default_value = "any")]
+        stream: crate::job::event::Stream,
+    },
+    #[command(about = "Act when the job prints nothing for a while")]
+    Silence {
+        #[arg(
+            help = "Seconds without output after which the hook fires.",
+            value_parser = clap::value_parser!(u64).range(1..)
+        )]
+        seconds: u64,
+        #[command(flatten)]
+        action: HookAction,
+        #[arg(
+            long,
+            short,
+            help = "Stream to watch for output.",
+            default_value = "any"
+        )]
+        stream: crate::job::event::Stream,
+    },
 }
@@ -0,0 +1,218 @@
+use crate::{
+    colors::Tend,
+    job::{Job, filter::Filter, format::Format},
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Jobs exported to a single file to be imported elsewhere.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub jobs: Vec<Job>,
+}
+
+#[derive(Debug)]
+pub struct ExportOptions {
+    /// Write to this file instead of stdout. Its extension selects the format.
+    pub output: Option<PathBuf>,
+    pub format: Option<Format>,
+    /// Store working directories below this directory relative to it.
+    pub relative_to: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub struct ImportOptions {
+    pub overwrite: bool,
+    /// Prepended to the name of every imported job and to dependencies on imported jobs.
+    pub rename_prefix: Option<String>,
+    /// Put every imported job into this group.
+    pub group: Option<String>,
+    /// Directory relative working directories are resolved against.
+    pub base: PathBuf,
+}
+
+pub fn export(filter: &Filter, options: &ExportOptions, verbose: bool) -> Result<()> {
+    let mut names = vec![];
+    Job::iterate_jobs_filtered(|job| names.push(job.name), filter, true, verbose)?;
+
+    let mut jobs = vec![];
+    for name in names {
+        // Export templates rather than the hooks they add.
+        let Some(mut job) = Job::load_unresolved(&name, verbose) else {
+            continue;
+        };
+        if let Some(base) = &options.relative_to
+            && let Ok(relative) = job.working_directory.strip_prefix(base)
+        {
+            job.working_directory = relative.to_path_buf();
+        }
+        strip_project(&mut job);
+        jobs.push(job);
+    }
+
+    if jobs.is_empty() {
+        anyhow::bail!("No jobs matched.");
+    }
+
+    let format = options
+        .format
+        .or_else(|| options.output.as_deref().map(Format::from_path))
+        .unwrap_or_default();
+    let mut content = format.serialize(&Bundle { jobs })?;
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+
+    match &options.output {
+        Some(output) => {
+            std::fs::write(output, content)?;
+            if verbose {
+                println!("Jobs exported to {}", output.display());
+            }
+        }
+        None => print!("{content}"),
+    }
+
+    Ok(())
+}
+
+/// Drops the project namespace from the name of a project job and from its dependencies on
+/// jobs of the same project, as imported jobs are not namespaced.
+fn strip_project(job: &mut Job) {
+    if job.project.take().is_none() {
+        return;
+    }
+    let Some((project, name)) = job.name.split_once('/') else {
+        return;
+    };
+    let prefix = format!("{project}/");
+    job.name = name.to_string();
+    for dependency in &mut job.depends_on {
+        if let Some(name) = dependency.strip_prefix(&prefix) {
+            *dependency = name.to_string();
+        }
+    }
+}
+
+/// Whether a job can be saved under this name.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\'])
+}
+
+/// Reads a bundle from a file, or from stdin if `path` is `-`.
+pub fn read(path: &Path, format: Option<Format>) -> Result<Bundle> {
+    let content = if path == Path::new("-") {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?
+    };
+
+    format
+        .unwrap_or_else(|| Format::from_path(path))
+        .deserialize(&content)
+        .with_context(|| format!("Invalid bundle {}", path.display()))
+}
+
+/// Saves jobs created by an import, applying the import options.
+pub fn import(jobs: Vec<Job>, options: &ImportOptions) {
+    let imported: Vec<String> = jobs.iter().map(|job| job.name.clone()).collect();
+
+    for mut job in jobs {
+        if let Some(prefix) = &options.rename_prefix {
+            job.name = format!("{prefix}{}", job.name);
+            for dependency in &mut job.depends_on {
+                if imported.contains(dependency) {
+                    *dependency = format!("{prefix}{dependency}");
+                }
+            }
+        }
+        if let Some(group) = &options.group {
+            job.group.clone_from(group);
+        }
+        job.working_directory = options.base.join(&job.working_directory);
+        if let Ok(working_directory) = job.working_directory.canonicalize() {
+            job.working_directory = working_directory;
+        }
+        job.project = None;
+
+        if !is_valid_name(&job.name) {
+            eprintln!(
+                "{} {}",
+                job.name.job(),
+                "not imported, job names cannot contain path separators".failure()
+            );
+            continue;
+        }
+
+        match job.save(options.overwrite) {
+            Ok(()) => println!("{} imported", job.name.job()),
+            Err(e)
+                if e.downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == std::io::ErrorKind::AlreadyExists) =>
+            {
+                eprintln!(
+                    "{} {}",
+                    job.name.job(),
+                    "already exists, use --overwrite to replace it".failure()
+                );
+            }
+            Err(e) => eprintln!("{} {}: {}", job.name.job(), "not imported".failure(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Bundle, is_valid_name, strip_project};
+    use crate::job::{Job, format::Format};
+    use std::path::PathBuf;
+
+    fn project_job(name: &str, depends_on: &[&str]) -> Job {
+        Job {
+            depends_on: depends_on.iter().map(ToString::to_string).collect(),
+            project: Some(PathBuf::from("shop/tend.toml")),
+            ..Job::new(
+                name.to_string(),
+                "true".to_string(),
+                vec![],
+                PathBuf::from("."),
+            )
+        }
+    }
+
+    #[test]
+    fn exported_project_jobs_can_be_imported() {
+        let mut jobs = vec![
+            project_job("shop/api", &["shop/db", "cache"]),
+            project_job("shop/db", &[]),
+        ];
+        assert!(!is_valid_name(&jobs[0].name));
+        jobs.iter_mut().for_each(strip_project);
+
+        for format in [Format::Json, Format::Toml, Format::Yaml] {
+            let content = format.serialize(&Bundle { jobs: jobs.clone() }).unwrap();
+            let bundle: Bundle = format.deserialize(&content).unwrap();
+            let names: Vec<&str> = bundle.jobs.iter().map(|job| job.name.as_str()).collect();
+            assert_eq!(names, ["api", "db"]);
+            assert!(names.iter().all(|name| is_valid_name(name)));
+            assert_eq!(bundle.jobs[0].depends_on, ["db", "cache"]);
+            assert_eq!(bundle.jobs[0].project, None);
+        }
+    }
+
+    #[test]
+    fn other_jobs_keep_their_names() {
+        let mut job = project_job("web", &["shop/api"]);
+        job.project = None;
+        strip_project(&mut job);
+        assert_eq!(job.name, "web");
+        assert_eq!(job.depends_on, ["shop/api"]);
+    }
+}
@@ -0,0 +1,23 @@
+use crate::job::format::Format;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// User settings read from `~/.tend/config.toml`.
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// Format of newly created job files.
+    #[serde(default)]
+    pub job_format: Format,
+}
+
+impl Config {
+    pub fn load() -> Result<Self> {
+        let path = crate::paths::config_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))
+    }
+}
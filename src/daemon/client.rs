@@ -0,0 +1,98 @@
+use super::protocol::{Request, Response};
+use crate::{
+    colors::Tend,
+    job::{Job, filter::Filter},
+};
+use anyhow::Result;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines},
+    net::{
+        UnixStream,
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+    },
+};
+
+#[derive(Debug)]
+pub struct Client {
+    reader: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl Client {
+    /// Connects to the daemon. Returns `None` if no daemon is running.
+    pub async fn connect() -> Result<Option<Self>> {
+        let path = crate::paths::socket_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        match UnixStream::connect(&path).await {
+            Ok(stream) => {
+                let (reader, writer) = stream.into_split();
+                Ok(Some(Self {
+                    reader: BufReader::new(reader).lines(),
+                    writer,
+                }))
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::NotFound
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn request(&mut self, request: &Request) -> Result<Response> {
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+
+        let line = self
+            .reader
+            .next_line()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Daemon closed the connection"))?;
+
+        match serde_json::from_str(&line)? {
+            Response::Error(e) => Err(anyhow::anyhow!(e)),
+            response @ (Response::Jobs(_) | Response::Status(_)) => Ok(response),
+        }
+    }
+
+    /// Loads the jobs matching `filter` and hands them over to the daemon.
+    pub async fn start(&mut self, filter: &Filter, verbose: bool) -> Result<()> {
+        let mut jobs = vec![];
+        Job::iterate_jobs_filtered(|job| jobs.push(job), filter, false, verbose)?;
+        if jobs.is_empty() {
+            anyhow::bail!("No jobs matched.");
+        }
+        let jobs = Job::resolve_dependencies(jobs, verbose)?;
+
+        let names: Vec<String> = jobs.iter().map(|job| job.name.clone()).collect();
+        let Response::Jobs(started) = self.request(&Request::Start { jobs }).await? else {
+            anyhow::bail!("Unexpected response from daemon.");
+        };
+
+        for name in names {
+            if started.contains(&name) {
+                println!("{} started", name.job());
+            } else {
+                println!("{} is already running", name.job());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Sends a single request to the daemon, failing if no daemon is running.
+pub async fn request(request: &Request) -> Result<Response> {
+    let mut client = Client::connect().await?.ok_or_else(|| {
+        anyhow::anyhow!("No daemon is running. Start one with `tend daemon start`.")
+    })?;
+    client.request(request).await
+}
@@ -0,0 +1,177 @@
+pub mod client;
+pub mod protocol;
+
+use self::{
+    client::Client,
+    protocol::{Request, Response},
+};
+use crate::{
+    colors::Tend,
+    job::JobCommand,
+    supervisor::{ShutdownSignals, Supervisor},
+};
+use anyhow::Result;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::{mpsc, oneshot},
+};
+
+type Envelope = (Request, oneshot::Sender<Response>);
+
+/// Runs the supervisor in this process and serves requests on the control socket until shut down.
+pub async fn serve(verbose: bool) -> Result<()> {
+    let path = crate::paths::socket_path()?;
+    if Client::connect().await?.is_some() {
+        anyhow::bail!("Daemon is already running.");
+    }
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    println!("{} listening on {}", "tend daemon".job(), path.display());
+
+    let (tx, mut rx) = mpsc::channel::<Envelope>(16);
+    let mut supervisor = Supervisor::new(verbose);
+    let mut signals = ShutdownSignals::new()?;
+    let mut connections = tokio::task::JoinSet::new();
+
+    loop {
+        tokio::select! {
+            connection = listener.accept() => {
+                match connection {
+                    Ok((stream, _)) => {
+                        connections.spawn(handle_connection(stream, tx.clone()));
+                    }
+                    Err(e) => eprintln!("{} {}", "Failed to accept connection:".failure(), e),
+                }
+            }
+
+            Some((request, reply)) = rx.recv() => {
+                let shutdown = matches!(request, Request::Shutdown);
+                let response = handle_request(&mut supervisor, request).await;
+                let _ = reply.send(response);
+                if shutdown {
+                    break;
+                }
+            }
+
+            Some((name, result)) = supervisor.join_next(), if !supervisor.is_empty() => {
+                supervisor.report(&name, &result);
+            }
+
+            () = signals.recv() => {
+                supervisor.shutdown().await;
+                break;
+            }
+        }
+    }
+
+    // Give clients a moment to receive their final responses.
+    drop(listener);
+    let _ = tokio::time::timeout(std::time::Duration::from_secs(1), connections.join_all()).await;
+
+    let _ = std::fs::remove_file(&path);
+    println!("{} stopped", "tend daemon".job());
+
+    Ok(())
+}
+
+async fn handle_request(supervisor: &mut Supervisor, request: Request) -> Response {
+    match request {
+        Request::Start { jobs } => {
+            let started = jobs
+                .into_iter()
+                .filter_map(|job| {
+                    let name = job.name.clone();
+                    supervisor.start(job).then_some(name)
+                })
+                .collect();
+            Response::Jobs(started)
+        }
+        Request::Stop { filter } => Response::Jobs(supervisor.send(JobCommand::Stop, &filter)),
+        Request::Restart { filter } => {
+            Response::Jobs(supervisor.send(JobCommand::Restart, &filter))
+        }
+        Request::Status { filter } => Response::Status(supervisor.statuses(&filter)),
+        Request::Shutdown => {
+            let stopped = supervisor.running(&crate::job::filter::Filter::All { exclude: vec![] });
+            supervisor.shutdown().await;
+            Response::Jobs(stopped)
+        }
+    }
+}
+
+async fn handle_connection(stream: UnixStream, tx: mpsc::Sender<Envelope>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                let (reply_tx, reply_rx) = oneshot::channel();
+                if tx.send((request, reply_tx)).await.is_err() {
+                    break;
+                }
+                reply_rx
+                    .await
+                    .unwrap_or_else(|_| Response::Error("Daemon is shutting down".to_string()))
+            }
+            Err(e) => Response::Error(format!("Invalid request: {e}")),
+        };
+
+        let Ok(mut line) = serde_json::to_string(&response) else {
+            break;
+        };
+        line.push('\n');
+        if writer.write_all(line.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Starts the daemon as a background process detached from this terminal.
+pub async fn spawn_detached(verbose: bool) -> Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    if Client::connect().await?.is_some() {
+        anyhow::bail!("Daemon is already running.");
+    }
+
+    let log_path = crate::paths::daemon_log_path()?;
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+
+    let mut command = std::process::Command::new(std::env::current_exe()?);
+    if verbose {
+        command.arg("--verbose");
+    }
+    command
+        .args(["daemon", "start", "--foreground"])
+        .stdin(std::process::Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .process_group(0);
+    let child = command.spawn()?;
+
+    for _ in 0..50 {
+        if Client::connect().await?.is_some() {
+            println!(
+                "{} started (pid {}), logging to {}",
+                "tend daemon".job(),
+                child.id(),
+                log_path.display()
+            );
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    anyhow::bail!(
+        "Daemon did not start in time. See {} for details.",
+        log_path.display()
+    )
+}
@@ -0,0 +1,21 @@
+use crate::job::{Job, filter::Filter, status::JobStatus};
+use serde::{Deserialize, Serialize};
+
+/// A request sent by a client to the daemon, serialized as a single line of JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Request {
+    Start { jobs: Vec<Job> },
+    Stop { filter: Filter },
+    Restart { filter: Filter },
+    Status { filter: Filter },
+    Shutdown,
+}
+
+/// The daemon's reply to a [`Request`], serialized as a single line of JSON.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum Response {
+    /// Names of the jobs affected by the request.
+    Jobs(Vec<String>),
+    Status(Vec<JobStatus>),
+    Error(String),
+}
@@ -0,0 +1,133 @@
+use crate::{
+    colors::Tend,
+    config::Config,
+    job::{Job, format::Format},
+};
+use anyhow::{Context, Result};
+use std::fmt::Write;
+
+fn editor_command() -> Vec<String> {
+    let editor = std::env::var("VISUAL")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+    editor.split_whitespace().map(ToString::to_string).collect()
+}
+
+fn header(job_name: &str, comment: &str, error: Option<&str>) -> String {
+    let mut header = format!(
+        "{comment} Editing job {job_name}. Lines starting with {comment} are ignored.\n\
+         {comment} Save and close the editor to apply the changes. An empty file cancels the edit.\n"
+    );
+    if let Some(error) = error {
+        let _ = writeln!(header, "{comment}");
+        for line in error.lines() {
+            let _ = writeln!(header, "{comment} error: {line}");
+        }
+    }
+    header
+}
+
+fn strip_comments(content: &str, comment: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with(comment))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Replaces comments with empty lines so that parse errors point to lines of the edited file.
+fn blank_comments(content: &str, comment: &str) -> String {
+    content
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with(comment) {
+                ""
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse(job_name: &str, format: Format, content: &str) -> Result<Job, String> {
+    let job: Job = format
+        .deserialize(content)
+        .map_err(|e| e.to_string().trim_end().to_string())?;
+    if job.name != job_name {
+        return Err(format!(
+            "the name cannot be changed here, use `tend edit {job_name} rename {}` instead",
+            job.name
+        ));
+    }
+    Ok(job)
+}
+
+/// Opens a job definition in `$VISUAL` or `$EDITOR` until it parses, then saves it.
+pub fn edit(job: &Job) -> Result<()> {
+    let format = match Job::find_file(&job.name)? {
+        Some(path) => Format::from_path(&path),
+        None => Config::load()?.job_format,
+    };
+    let comment = format.comment();
+    let original = format.serialize(job)?;
+    let mut content = original.clone();
+    let mut error = None;
+
+    let file = tempfile::Builder::new()
+        .prefix(&format!("tend-{}-", job.name))
+        .suffix(&format!(".{}", format.extension()))
+        .tempfile()?;
+
+    loop {
+        std::fs::write(
+            file.path(),
+            format!(
+                "{}{content}\n",
+                header(&job.name, comment, error.as_deref())
+            ),
+        )?;
+
+        let command = editor_command();
+        let status = std::process::Command::new(&command[0])
+            .args(&command[1..])
+            .arg(file.path())
+            .status()
+            .with_context(|| format!("Could not start editor {}", command.join(" ")))?;
+        if !status.success() {
+            anyhow::bail!("Editor exited with {status}, job was not changed.");
+        }
+
+        let edited = std::fs::read_to_string(file.path())?;
+        content = strip_comments(&edited, comment);
+        if content.trim().is_empty() {
+            println!("Edit cancelled, job {} was not changed.", job.name.job());
+            return Ok(());
+        }
+        if content.trim() == original.trim() {
+            println!("No changes made to job {}.", job.name.job());
+            return Ok(());
+        }
+
+        match parse(&job.name, format, &blank_comments(&edited, comment)) {
+            Ok(edited) => {
+                edited.save(true)?;
+                println!("Job {} saved.", job.name.job());
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{} {}", "Invalid job definition:".failure(), e);
+                error = Some(e);
+            }
+        }
+    }
+}
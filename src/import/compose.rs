@@ -0,0 +1,369 @@
+use crate::{
+    colors::Tend,
+    job::{Job, event::RestartBehavior},
+};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Deserialize)]
+struct ComposeFile {
+    name: Option<String>,
+    #[serde(default)]
+    services: BTreeMap<String, Service>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Service {
+    command: Option<Command>,
+    entrypoint: Option<Command>,
+    #[serde(default)]
+    environment: Environment,
+    working_dir: Option<PathBuf>,
+    #[serde(default)]
+    depends_on: DependsOn,
+    restart: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Command {
+    String(String),
+    List(Vec<String>),
+}
+
+impl Command {
+    /// Splits the command into words. `$$`, which escapes `$` from compose interpolation,
+    /// becomes `$`.
+    fn words(&self) -> Result<Vec<String>> {
+        let words = match self {
+            Self::String(command) => shell_words::split(command)?,
+            Self::List(words) => words.clone(),
+        };
+        Ok(words.iter().map(|word| word.replace("$$", "$")).collect())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Environment {
+    Map(BTreeMap<String, Option<serde_yaml::Value>>),
+    List(Vec<String>),
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::List(vec![])
+    }
+}
+
+impl Environment {
+    /// Variables with a value. Variables without one are passed through from the host, which
+    /// jobs do anyway.
+    fn vars(&self) -> BTreeMap<String, String> {
+        match self {
+            Self::Map(vars) => vars
+                .iter()
+                .filter_map(|(key, value)| {
+                    let value = match value.as_ref()? {
+                        serde_yaml::Value::String(value) => value.clone(),
+                        serde_yaml::Value::Bool(value) => value.to_string(),
+                        serde_yaml::Value::Number(value) => value.to_string(),
+                        _ => return None,
+                    };
+                    Some((key.clone(), value))
+                })
+                .collect(),
+            Self::List(vars) => vars
+                .iter()
+                .filter_map(|var| var.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DependsOn {
+    List(Vec<String>),
+    Map(BTreeMap<String, serde_yaml::Value>),
+}
+
+impl Default for DependsOn {
+    fn default() -> Self {
+        Self::List(vec![])
+    }
+}
+
+impl DependsOn {
+    fn names(&self) -> Vec<String> {
+        match self {
+            Self::List(names) => names.clone(),
+            Self::Map(names) => names.keys().cloned().collect(),
+        }
+    }
+}
+
+/// The restart behavior of a restart policy, and the retry limit of `on-failure:N`.
+fn restart_behavior(restart: Option<&str>) -> Result<(RestartBehavior, Option<u64>)> {
+    Ok(match restart {
+        None | Some("no") => (RestartBehavior::Never, None),
+        Some("always" | "unless-stopped") => (RestartBehavior::Always, None),
+        Some("on-failure") => (RestartBehavior::OnFailure, None),
+        Some(restart) => match restart.strip_prefix("on-failure:") {
+            Some(retries) => (
+                RestartBehavior::OnFailure,
+                Some(
+                    retries
+                        .parse()
+                        .with_context(|| format!("invalid restart policy {restart}"))?,
+                ),
+            ),
+            None => anyhow::bail!("unknown restart policy {restart}"),
+        },
+    })
+}
+
+/// Reads the services of a docker-compose file that define a `command` or `entrypoint`.
+/// Services that only run an image are skipped, as are dependencies on them.
+pub fn read(path: &Path) -> Result<Vec<Job>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let file: ComposeFile =
+        serde_yaml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+    let path = path.canonicalize()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("/"));
+    let group = file.name.clone().unwrap_or_else(|| {
+        dir.file_name().map_or_else(
+            || "default".to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        )
+    });
+
+    let (services, skipped): (BTreeMap<_, _>, BTreeMap<_, _>) = file
+        .services
+        .into_iter()
+        .partition(|(_, service)| service.command.is_some() || service.entrypoint.is_some());
+    for name in skipped.keys() {
+        eprintln!(
+            "{} {}",
+            name.job(),
+            "skipped, services without a command or entrypoint are not supported".failure()
+        );
+    }
+
+    let mut jobs = vec![];
+    for (name, service) in &services {
+        let invalid = || format!("Invalid service {name} in {}", path.display());
+
+        let mut words = vec![];
+        if let Some(entrypoint) = &service.entrypoint {
+            words.extend(entrypoint.words().with_context(invalid)?);
+        }
+        if let Some(command) = &service.command {
+            words.extend(command.words().with_context(invalid)?);
+        }
+        let mut words = words.into_iter();
+        let Some(program) = words.next() else {
+            anyhow::bail!("{}: empty command", invalid());
+        };
+
+        // Absolute working directories are paths inside the container.
+        let working_directory = match &service.working_dir {
+            Some(working_dir) if working_dir.is_absolute() => {
+                eprintln!(
+                    "{} {} {}",
+                    name.job(),
+                    "ignored working_dir inside the container:".failure(),
+                    working_dir.display()
+                );
+                dir.to_path_buf()
+            }
+            Some(working_dir) => dir.join(working_dir),
+            None => dir.to_path_buf(),
+        };
+        let mut job = Job::new(name.clone(), program, words.collect(), working_directory);
+        job.group.clone_from(&group);
+        job.env = service.environment.vars();
+        (job.restart, job.max_restarts) =
+            restart_behavior(service.restart.as_deref()).with_context(invalid)?;
+
+        for dependency in service.depends_on.names() {
+            if services.contains_key(&dependency) {
+                job.depends_on.push(dependency);
+            } else {
+                eprintln!(
+                    "{} {} {}",
+                    name.job(),
+                    "dropped dependency on service without a command".failure(),
+                    dependency.job()
+                );
+            }
+        }
+
+        jobs.push(job);
+    }
+
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read;
+    use crate::job::{Job, event::RestartBehavior};
+    use std::collections::BTreeMap;
+
+    fn jobs(content: &str) -> anyhow::Result<Vec<Job>> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compose.yaml");
+        std::fs::write(&path, content).unwrap();
+        read(&path)
+    }
+
+    fn env(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_environment_lists_and_maps() {
+        let jobs = jobs(
+            "name: shop
+services:
+  list:
+    command: run
+    environment:
+      - PORT=80
+      - URL=a=b
+      - FROM_HOST
+  map:
+    command: run
+    environment:
+      PORT: 80
+      DEBUG: true
+      NAME: web
+      FROM_HOST:
+",
+        )
+        .unwrap();
+        assert_eq!(jobs[0].name, "list");
+        assert_eq!(jobs[0].group, "shop");
+        assert_eq!(jobs[0].env, env(&[("PORT", "80"), ("URL", "a=b")]));
+        assert_eq!(
+            jobs[1].env,
+            env(&[("DEBUG", "true"), ("NAME", "web"), ("PORT", "80")])
+        );
+    }
+
+    #[test]
+    fn reads_dependency_lists_and_maps() {
+        let jobs = jobs(
+            "services:
+  api:
+    command: run
+    depends_on: [db, cache]
+  web:
+    command: run
+    depends_on:
+      api:
+        condition: service_started
+  db:
+    command: run
+  cache:
+    image: redis
+",
+        )
+        .unwrap();
+        let names: Vec<&str> = jobs.iter().map(|job| job.name.as_str()).collect();
+        assert_eq!(names, ["api", "db", "web"]);
+        assert_eq!(jobs[0].depends_on, ["db"]);
+        assert_eq!(jobs[2].depends_on, ["api"]);
+    }
+
+    #[test]
+    fn unescapes_dollars_in_commands() {
+        let jobs = jobs(
+            "services:
+  string:
+    entrypoint: sh -c
+    command: \"'echo $$HOME'\"
+  list:
+    command: [echo, \"$$1\"]
+",
+        )
+        .unwrap();
+        assert_eq!(jobs[0].program, "echo");
+        assert_eq!(jobs[0].args, ["$1"]);
+        assert_eq!(jobs[1].program, "sh");
+        assert_eq!(jobs[1].args, ["-c", "echo $HOME"]);
+    }
+
+    #[test]
+    fn maps_restart_policies() {
+        let jobs = jobs(
+            "services:
+  a:
+    command: run
+    restart: on-failure:3
+  b:
+    command: run
+    restart: on-failure
+  c:
+    command: run
+    restart: unless-stopped
+  d:
+    command: run
+",
+        )
+        .unwrap();
+        let restarts: Vec<_> = jobs
+            .iter()
+            .map(|job| (job.restart, job.max_restarts))
+            .collect();
+        assert_eq!(
+            restarts,
+            [
+                (RestartBehavior::OnFailure, Some(3)),
+                (RestartBehavior::OnFailure, None),
+                (RestartBehavior::Always, None),
+                (RestartBehavior::Never, None),
+            ]
+        );
+        assert!(jobs_with_restart("on-failure:x").is_err());
+        assert!(jobs_with_restart("on-failures").is_err());
+    }
+
+    fn jobs_with_restart(restart: &str) -> anyhow::Result<Vec<Job>> {
+        jobs(&format!(
+            "services:\n  a:\n    command: run\n    restart: {restart}\n"
+        ))
+    }
+
+    #[test]
+    fn ignores_working_directories_inside_the_container() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("compose.yaml");
+        std::fs::write(
+            &path,
+            "services:
+  app:
+    command: run
+    working_dir: /app
+  sub:
+    command: run
+    working_dir: sub
+",
+        )
+        .unwrap();
+        let jobs = read(&path).unwrap();
+        let dir = dir.path().canonicalize().unwrap();
+        assert_eq!(jobs[0].working_directory, dir);
+        assert_eq!(jobs[1].working_directory, dir.join("sub"));
+    }
+}
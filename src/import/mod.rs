@@ -0,0 +1,20 @@
+//! Importers creating jobs from process definitions of other tools.
+
+use crate::colors::Tend;
+
+pub mod compose;
+pub mod pm2;
+pub mod procfile;
+pub mod supervisord;
+
+/// Reports settings of a process that have no tend equivalent and were not imported.
+fn report_unsupported(name: &str, keys: &[String]) {
+    if !keys.is_empty() {
+        eprintln!(
+            "{} {} {}",
+            name.job(),
+            "ignored unsupported settings:".failure(),
+            keys.join(", ")
+        );
+    }
+}
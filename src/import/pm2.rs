@@ -0,0 +1,268 @@
+use crate::job::{
+    Job,
+    event::{RestartBehavior, RestartStrategy},
+    signal::Signal,
+};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Ecosystem {
+    Apps { apps: Vec<App> },
+    List(Vec<App>),
+}
+
+#[derive(Debug, Deserialize)]
+struct App {
+    name: Option<String>,
+    script: String,
+    #[serde(default)]
+    args: Args,
+    interpreter: Option<String>,
+    #[serde(default, alias = "node_args")]
+    interpreter_args: Args,
+    cwd: Option<PathBuf>,
+    #[serde(default)]
+    env: BTreeMap<String, serde_json::Value>,
+    autorestart: Option<bool>,
+    max_restarts: Option<u64>,
+    /// Milliseconds between SIGINT and SIGKILL when stopping.
+    kill_timeout: Option<u64>,
+    exp_backoff_restart_delay: Option<u64>,
+    /// Settings without a tend equivalent, reported when importing.
+    #[serde(flatten)]
+    unsupported: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Args {
+    String(String),
+    List(Vec<String>),
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self::List(vec![])
+    }
+}
+
+impl Args {
+    fn words(&self) -> Result<Vec<String>> {
+        Ok(match self {
+            Self::String(args) => shell_words::split(args)?,
+            Self::List(args) => args.clone(),
+        })
+    }
+}
+
+/// Interpreter pm2 picks for a script by its extension.
+fn default_interpreter(script: &str) -> Option<&'static str> {
+    let extension = Path::new(script).extension()?.to_str()?;
+    Some(match extension {
+        "js" | "cjs" | "mjs" => "node",
+        "ts" => "ts-node",
+        "py" => "python3",
+        "sh" => "bash",
+        "rb" => "ruby",
+        "php" => "php",
+        "pl" => "perl",
+        _ => return None,
+    })
+}
+
+fn env_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn app(app: &App, dir: &Path) -> Result<Job> {
+    let name = app.name.clone().unwrap_or_else(|| {
+        Path::new(&app.script).file_stem().map_or_else(
+            || app.script.clone(),
+            |stem| stem.to_string_lossy().into_owned(),
+        )
+    });
+
+    let interpreter = app
+        .interpreter
+        .as_deref()
+        .or_else(|| default_interpreter(&app.script))
+        .filter(|interpreter| *interpreter != "none");
+    let (program, mut args) = match interpreter {
+        Some(interpreter) => {
+            let mut args = app.interpreter_args.words()?;
+            args.push(app.script.clone());
+            (interpreter.to_string(), args)
+        }
+        None => (app.script.clone(), vec![]),
+    };
+    args.extend(app.args.words()?);
+
+    let working_directory = app
+        .cwd
+        .as_ref()
+        .map_or_else(|| dir.to_path_buf(), |cwd| dir.join(cwd));
+    let mut job = Job::new(name.clone(), program, args, working_directory);
+    job.env = app
+        .env
+        .iter()
+        .map(|(key, value)| (key.clone(), env_value(value)))
+        .collect();
+    // pm2 restarts every app that exits unless told otherwise, without a delay.
+    job.restart = if app.autorestart.unwrap_or(true) {
+        RestartBehavior::Always
+    } else {
+        RestartBehavior::Never
+    };
+    job.restart_strategy = if app.exp_backoff_restart_delay.is_some() {
+        RestartStrategy::ExponentialBackoff
+    } else {
+        RestartStrategy::Immediate
+    };
+    // pm2 gives up after 16 unstable restarts by default.
+    job.max_restarts = Some(app.max_restarts.unwrap_or(16));
+    // pm2 stops apps with SIGINT and kills them after 1.6 seconds by default.
+    job.stop_signal = Signal::Int;
+    job.stop_timeout_seconds = app.kill_timeout.unwrap_or(1600).div_ceil(1000);
+
+    let unsupported: Vec<String> = app.unsupported.keys().cloned().collect();
+    super::report_unsupported(&name, &unsupported);
+    Ok(job)
+}
+
+/// Reads the apps of a pm2 ecosystem file in JSON. Apps run in the file's directory unless
+/// they set `cwd`, and are put into a group named after that directory.
+pub fn read(path: &Path) -> Result<Vec<Job>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let ecosystem: Ecosystem =
+        serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?;
+    let path = path.canonicalize()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("/"));
+    let group = dir.file_name().map_or_else(
+        || "default".to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+
+    let apps = match ecosystem {
+        Ecosystem::Apps { apps } | Ecosystem::List(apps) => apps,
+    };
+    let mut jobs = vec![];
+    for app in apps {
+        let mut job = self::app(&app, dir)
+            .with_context(|| format!("Invalid app {} in {}", app.script, path.display()))?;
+        job.group.clone_from(&group);
+        jobs.push(job);
+    }
+
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{App, app, default_interpreter};
+    use crate::job::{Job, event::RestartStrategy, signal::Signal};
+    use std::path::Path;
+
+    fn parse(app: serde_json::Value) -> App {
+        serde_json::from_value(app).unwrap()
+    }
+
+    fn job(app: serde_json::Value) -> Job {
+        super::app(&parse(app), Path::new("/srv")).unwrap()
+    }
+
+    #[test]
+    fn picks_interpreters_by_extension() {
+        assert_eq!(default_interpreter("app.js"), Some("node"));
+        assert_eq!(default_interpreter("dist/app.mjs"), Some("node"));
+        assert_eq!(default_interpreter("app.ts"), Some("ts-node"));
+        assert_eq!(default_interpreter("worker.py"), Some("python3"));
+        assert_eq!(default_interpreter("run.sh"), Some("bash"));
+        assert_eq!(default_interpreter("server"), None);
+        assert_eq!(default_interpreter("app.exe"), None);
+    }
+
+    #[test]
+    fn builds_commands() {
+        let job = self::job(serde_json::json!({
+            "script": "server.js",
+            "node_args": "--inspect --max-old-space-size=512",
+            "args": "--port 80 'a b'",
+        }));
+        assert_eq!(job.name, "server");
+        assert_eq!(job.program, "node");
+        assert_eq!(
+            job.args,
+            [
+                "--inspect",
+                "--max-old-space-size=512",
+                "server.js",
+                "--port",
+                "80",
+                "a b"
+            ]
+        );
+
+        let job = self::job(serde_json::json!({
+            "name": "api",
+            "script": "./api",
+            "interpreter": "none",
+            "args": ["--name", "a b"],
+            "cwd": "api",
+        }));
+        assert_eq!(job.name, "api");
+        assert_eq!(job.program, "./api");
+        assert_eq!(job.args, ["--name", "a b"]);
+        assert_eq!(job.working_directory, Path::new("/srv/api"));
+    }
+
+    #[test]
+    fn maps_restarts_and_stopping() {
+        let job = self::job(serde_json::json!({ "script": "app.js" }));
+        assert_eq!(job.max_restarts, Some(16));
+        assert!(matches!(job.restart_strategy, RestartStrategy::Immediate));
+        assert!(matches!(job.stop_signal, Signal::Int));
+        assert_eq!(job.stop_timeout_seconds, 2);
+
+        let job = self::job(serde_json::json!({
+            "script": "app.js",
+            "max_restarts": 3,
+            "kill_timeout": 3000,
+            "exp_backoff_restart_delay": 100,
+        }));
+        assert_eq!(job.max_restarts, Some(3));
+        assert!(matches!(
+            job.restart_strategy,
+            RestartStrategy::ExponentialBackoff
+        ));
+        assert_eq!(job.stop_timeout_seconds, 3);
+
+        let job = self::job(serde_json::json!({ "script": "app.js", "kill_timeout": 1 }));
+        assert_eq!(job.stop_timeout_seconds, 1);
+    }
+
+    #[test]
+    fn collects_unsupported_settings() {
+        let app = parse(serde_json::json!({
+            "script": "app.js",
+            "env": { "PORT": 80, "NAME": "web" },
+            "instances": 4,
+            "watch": true,
+        }));
+        let unsupported: Vec<&str> = app.unsupported.keys().map(String::as_str).collect();
+        assert_eq!(unsupported, ["instances", "watch"]);
+
+        let job = self::app(&app, Path::new("/srv")).unwrap();
+        assert_eq!(job.env["PORT"], "80");
+        assert_eq!(job.env["NAME"], "web");
+    }
+}
@@ -0,0 +1,176 @@
+use crate::job::Job;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Port assigned to the first process that uses `$PORT`, unless the `.env` file sets `PORT`.
+/// Each following process gets the next hundred, as foreman does.
+const BASE_PORT: u16 = 5000;
+
+/// Characters that need a shell to be interpreted.
+const SHELL_SYNTAX: [char; 10] = ['$', '|', '&', ';', '<', '>', '`', '(', ')', '*'];
+
+/// Reads a Procfile. Every `name: command` line becomes a job running in the Procfile's
+/// directory, in a group named after that directory. A `.env` file next to the Procfile is
+/// loaded by every job.
+pub fn read(path: &Path) -> Result<Vec<Job>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let path = path.canonicalize()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("/"));
+    let group = dir.file_name().map_or_else(
+        || "default".to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let env_file = dir.join(".env");
+    let mut port = env_file
+        .is_file()
+        .then(|| dotenvy::from_path_iter(&env_file).ok())
+        .flatten()
+        .and_then(|mut vars| vars.find_map(|var| var.ok().filter(|(key, _)| key == "PORT")))
+        .and_then(|(_, port)| port.parse().ok())
+        .unwrap_or(BASE_PORT);
+
+    let mut jobs = vec![];
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((name, command)) = line.split_once(':') else {
+            anyhow::bail!(
+                "{}:{}: expected `name: command`",
+                path.display(),
+                number + 1
+            );
+        };
+        let name = name.trim();
+        let command = command.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!(
+                "{}:{}: invalid process name {name}",
+                path.display(),
+                number + 1
+            );
+        }
+
+        let (program, args) = if command.contains(SHELL_SYNTAX) {
+            (
+                "sh".to_string(),
+                vec!["-c".to_string(), command.to_string()],
+            )
+        } else {
+            let mut words = shell_words::split(command)
+                .with_context(|| format!("{}:{}", path.display(), number + 1))?
+                .into_iter();
+            let program = words.next().ok_or_else(|| {
+                anyhow::anyhow!("{}:{}: empty command", path.display(), number + 1)
+            })?;
+            (program, words.collect())
+        };
+
+        let mut job = Job::new(name.to_string(), program, args, dir.to_path_buf());
+        job.group.clone_from(&group);
+        if env_file.is_file() {
+            job.env_file = Some(".env".into());
+        }
+        if command.contains("$PORT") || command.contains("${PORT}") {
+            job.env.insert("PORT".to_string(), port.to_string());
+            port += 100;
+        }
+        jobs.push(job);
+    }
+
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read;
+    use crate::job::Job;
+    use std::path::PathBuf;
+
+    /// Reads `procfile` from a temporary directory, next to `env` as `.env` if given.
+    fn jobs(procfile: &str, env: Option<&str>) -> anyhow::Result<(Vec<Job>, PathBuf)> {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("Procfile"), procfile).unwrap();
+        if let Some(env) = env {
+            std::fs::write(dir.path().join(".env"), env).unwrap();
+        }
+        let jobs = read(&dir.path().join("Procfile"))?;
+        Ok((jobs, dir.path().canonicalize().unwrap()))
+    }
+
+    fn port(job: &Job) -> Option<&str> {
+        job.env.get("PORT").map(String::as_str)
+    }
+
+    #[test]
+    fn reads_processes() {
+        let (jobs, dir) = jobs(
+            "# comment\n\
+            \n\
+            web: bundle exec rails server -b '0.0.0.0'\n\
+            worker_1:python worker.py\n",
+            None,
+        )
+        .unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name, "web");
+        assert_eq!(jobs[0].program, "bundle");
+        assert_eq!(jobs[0].args, ["exec", "rails", "server", "-b", "0.0.0.0"]);
+        assert_eq!(jobs[0].working_directory, dir);
+        assert_eq!(jobs[0].group, dir.file_name().unwrap().to_string_lossy());
+        assert_eq!(jobs[0].env_file, None);
+        assert_eq!(jobs[1].name, "worker_1");
+        assert_eq!(jobs[1].program, "python");
+    }
+
+    #[test]
+    fn runs_shell_syntax_in_a_shell() {
+        let (jobs, _) = jobs("web: cd app && npm start\nlog: tail -f *.log\n", None).unwrap();
+        assert_eq!(jobs[0].program, "sh");
+        assert_eq!(jobs[0].args, ["-c", "cd app && npm start"]);
+        assert_eq!(jobs[1].program, "sh");
+        assert_eq!(jobs[1].args, ["-c", "tail -f *.log"]);
+    }
+
+    #[test]
+    fn assigns_ports_to_processes_using_them() {
+        let (jobs, _) = jobs(
+            "web: serve --port $PORT\n\
+            worker: work\n\
+            api: serve --port ${PORT}\n",
+            None,
+        )
+        .unwrap();
+        assert_eq!(port(&jobs[0]), Some("5000"));
+        assert_eq!(port(&jobs[1]), None);
+        assert_eq!(port(&jobs[2]), Some("5100"));
+    }
+
+    #[test]
+    fn starts_ports_at_the_port_of_the_env_file() {
+        let (jobs, _) = jobs(
+            "web: serve $PORT\napi: serve $PORT\n",
+            Some("DEBUG=1\nPORT=8000\n"),
+        )
+        .unwrap();
+        assert_eq!(port(&jobs[0]), Some("8000"));
+        assert_eq!(port(&jobs[1]), Some("8100"));
+        assert_eq!(jobs[0].env_file, Some(".env".into()));
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        let error = |procfile| jobs(procfile, None).unwrap_err().to_string();
+        assert!(error("web: run\nweb/api: run\n").ends_with(":2: invalid process name web/api"));
+        assert!(error(": run\n").ends_with(":1: invalid process name "));
+        assert!(error("just a command\n").ends_with(":1: expected `name: command`"));
+        assert!(error("web:\n").ends_with(":1: empty command"));
+    }
+}
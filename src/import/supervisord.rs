@@ -0,0 +1,381 @@
+use crate::{
+    colors::Tend,
+    job::{Job, event::RestartBehavior, signal::Signal},
+};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Sections configuring supervisord itself rather than a process.
+const DAEMON_SECTIONS: [&str; 5] = [
+    "supervisord",
+    "supervisorctl",
+    "unix_http_server",
+    "inet_http_server",
+    "rpcinterface:",
+];
+
+#[derive(Debug)]
+struct Section {
+    name: String,
+    keys: Vec<(String, String)>,
+}
+
+/// Parses INI the way supervisord does: `;` and `#` start comment lines, ` ;` starts an
+/// inline comment and indented lines continue the value of the previous key.
+fn parse_ini(path: &Path, content: &str) -> Result<Vec<Section>> {
+    let mut sections: Vec<Section> = vec![];
+    for (number, line) in content.lines().enumerate() {
+        let error = |message: &str| anyhow::anyhow!("{}:{}: {message}", path.display(), number + 1);
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with([';', '#']) {
+            continue;
+        }
+        let value = trimmed
+            .find(" ;")
+            .or_else(|| trimmed.find("\t;"))
+            .map_or(trimmed, |comment| trimmed[..comment].trim_end());
+
+        if line.starts_with(char::is_whitespace) {
+            let (_, previous) = sections
+                .last_mut()
+                .and_then(|section| section.keys.last_mut())
+                .ok_or_else(|| error("continuation line without a key"))?;
+            previous.push('\n');
+            previous.push_str(value);
+        } else if let Some(name) = value.strip_prefix('[') {
+            let name = name
+                .strip_suffix(']')
+                .ok_or_else(|| error("expected `]`"))?;
+            sections.push(Section {
+                name: name.trim().to_string(),
+                keys: vec![],
+            });
+        } else {
+            let separator = value
+                .find(['=', ':'])
+                .ok_or_else(|| error("expected `key = value`"))?;
+            let section = sections
+                .last_mut()
+                .ok_or_else(|| error("key outside of a section"))?;
+            section.keys.push((
+                value[..separator].trim().to_lowercase(),
+                value[separator + 1..].trim().to_string(),
+            ));
+        }
+    }
+    Ok(sections)
+}
+
+/// Expands `%(name)s` expressions supervisord supports in process settings.
+fn expand(value: &str, variables: &BTreeMap<&str, String>) -> Result<String> {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(after) = rest.strip_prefix('%') {
+            expanded.push('%');
+            rest = after;
+            continue;
+        }
+
+        let Some((name, after)) = rest
+            .strip_prefix('(')
+            .and_then(|rest| rest.split_once(")s"))
+        else {
+            anyhow::bail!("invalid expression in {value}");
+        };
+        let variable = name.strip_prefix("ENV_").map_or_else(
+            || variables.get(name).cloned(),
+            |name| std::env::var(name).ok(),
+        );
+        expanded.push_str(
+            &variable.ok_or_else(|| anyhow::anyhow!("unknown variable {name} in {value}"))?,
+        );
+        rest = after;
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Parses `KEY="value",KEY2=value2` as used by the `environment` setting.
+fn parse_environment(value: &str) -> Result<BTreeMap<String, String>> {
+    let mut pairs = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+    for c in value.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ',') => pairs.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if quote.is_some() {
+        anyhow::bail!("unterminated quote in environment");
+    }
+    pairs.push(current);
+
+    pairs
+        .iter()
+        .map(|pair| pair.trim())
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+                .ok_or_else(|| anyhow::anyhow!("expected KEY=value in environment, got {pair}"))
+        })
+        .collect()
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => anyhow::bail!("expected true or false, got {value}"),
+    }
+}
+
+fn restart_behavior(autorestart: &str) -> Result<RestartBehavior> {
+    if autorestart.eq_ignore_ascii_case("unexpected") {
+        return Ok(RestartBehavior::OnFailure);
+    }
+    Ok(if parse_bool(autorestart)? {
+        RestartBehavior::Always
+    } else {
+        RestartBehavior::Never
+    })
+}
+
+fn program(
+    name: &str,
+    keys: &[(String, String)],
+    variables: &BTreeMap<&str, String>,
+    dir: &Path,
+) -> Result<Job> {
+    let mut command = None;
+    let mut working_directory = dir.to_path_buf();
+    let mut env = BTreeMap::new();
+    // supervisord restarts processes that exit with an unexpected code by default.
+    let mut restart = RestartBehavior::OnFailure;
+    let mut max_restarts = Some(3);
+    let mut enabled = true;
+    let mut stop_signal = Signal::Term;
+    let mut stop_timeout_seconds = 10;
+    let mut unsupported = vec![];
+
+    for (key, value) in keys {
+        let value = expand(value, variables)?;
+        match key.as_str() {
+            "command" => command = Some(value),
+            "directory" => working_directory = dir.join(PathBuf::from(value)),
+            "environment" => env = parse_environment(&value)?,
+            "autorestart" => restart = restart_behavior(&value)?,
+            "startretries" => max_restarts = Some(value.parse()?),
+            "autostart" => enabled = parse_bool(&value)?,
+            "stopsignal" => {
+                stop_signal = Signal::from_str(&value, true)
+                    .map_err(|e| anyhow::anyhow!("unsupported stopsignal {value}: {e}"))?;
+            }
+            "stopwaitsecs" => stop_timeout_seconds = value.parse()?,
+            // Only the default is supported, where 0 is the one expected exit code.
+            "exitcodes" if value == "0" => (),
+            "process_name" if value == name => (),
+            "numprocs" if value == "1" => (),
+            _ => unsupported.push(key.clone()),
+        }
+    }
+    super::report_unsupported(name, &unsupported);
+
+    let command = command.ok_or_else(|| anyhow::anyhow!("missing command"))?;
+    let mut words = shell_words::split(&command)?.into_iter();
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("empty command"))?;
+
+    let mut job = Job::new(
+        name.to_string(),
+        program,
+        words.collect(),
+        working_directory,
+    );
+    job.enabled = enabled;
+    job.env = env;
+    job.restart = restart;
+    job.max_restarts = max_restarts;
+    job.stop_signal = stop_signal;
+    job.stop_timeout_seconds = stop_timeout_seconds;
+    Ok(job)
+}
+
+/// Reads the `[program:x]` sections of a supervisord configuration file. Programs are put into
+/// the group of their `[group:x]` section, or a group named after the file.
+pub fn read(path: &Path) -> Result<Vec<Job>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read {}", path.display()))?;
+    let sections = parse_ini(path, &content)?;
+    let path = path.canonicalize()?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("/"));
+    let default_group = path.file_stem().map_or_else(
+        || "default".to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+
+    let mut groups = BTreeMap::new();
+    for section in &sections {
+        let Some(group) = section.name.strip_prefix("group:") else {
+            continue;
+        };
+        for (key, value) in &section.keys {
+            if key == "programs" {
+                for program in value.split(',') {
+                    groups.insert(program.trim().to_string(), group.to_string());
+                }
+            }
+        }
+    }
+
+    let mut jobs = vec![];
+    for section in &sections {
+        let Some(name) = section.name.strip_prefix("program:") else {
+            if !section.name.starts_with("group:")
+                && !DAEMON_SECTIONS
+                    .iter()
+                    .any(|daemon| section.name.starts_with(daemon))
+            {
+                eprintln!(
+                    "{} {}",
+                    format!("[{}]", section.name).job(),
+                    "skipped, only program and group sections are supported".failure()
+                );
+            }
+            continue;
+        };
+
+        let group = groups.get(name).unwrap_or(&default_group);
+        let variables = BTreeMap::from([
+            ("here", dir.to_string_lossy().into_owned()),
+            ("program_name", name.to_string()),
+            ("group_name", group.clone()),
+            ("process_num", "0".to_string()),
+            ("numprocs", "1".to_string()),
+        ]);
+        let mut job = program(name, &section.keys, &variables, dir)
+            .with_context(|| format!("Invalid program {name} in {}", path.display()))?;
+        job.group.clone_from(group);
+        jobs.push(job);
+    }
+
+    Ok(jobs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand, parse_environment, parse_ini};
+    use std::{collections::BTreeMap, path::Path};
+
+    fn ini(content: &str) -> Vec<(String, Vec<(String, String)>)> {
+        parse_ini(Path::new("supervisord.conf"), content)
+            .unwrap()
+            .into_iter()
+            .map(|section| (section.name, section.keys))
+            .collect()
+    }
+
+    fn keys(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_sections_and_comments() {
+        let sections = ini("; comment\n\
+            # another comment\n\
+            [program:web]\n\
+            Command = python app.py ; inline comment\n\
+            directory: /srv/web\n\
+            url=http://example.com/a;b\n\
+            \n\
+            [ group:site ]\n\
+            programs=web\n");
+        assert_eq!(
+            sections,
+            [
+                (
+                    "program:web".to_string(),
+                    keys(&[
+                        ("command", "python app.py"),
+                        ("directory", "/srv/web"),
+                        ("url", "http://example.com/a;b"),
+                    ])
+                ),
+                ("group:site".to_string(), keys(&[("programs", "web")])),
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_continuation_lines() {
+        let sections =
+            ini("[program:web]\nenvironment=A=\"1\",\n    B=\"2\" ; comment\ncommand=run\n");
+        assert_eq!(
+            sections[0].1,
+            keys(&[("environment", "A=\"1\",\nB=\"2\""), ("command", "run")])
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let path = Path::new("supervisord.conf");
+        let error = parse_ini(path, "command=run\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "supervisord.conf:1: key outside of a section"
+        );
+        assert!(parse_ini(path, "  command=run\n").is_err());
+        assert!(parse_ini(path, "[program:web\n").is_err());
+        assert!(parse_ini(path, "[program:web]\ncommand\n").is_err());
+    }
+
+    #[test]
+    fn expands_variables() {
+        let variables = BTreeMap::from([
+            ("program_name", "web".to_string()),
+            ("here", "/etc/supervisor".to_string()),
+        ]);
+        assert_eq!(
+            expand("%(here)s/%(program_name)s.log at 100%%", &variables).unwrap(),
+            "/etc/supervisor/web.log at 100%"
+        );
+        assert_eq!(
+            expand("%(ENV_PATH)s", &variables).unwrap(),
+            std::env::var("PATH").unwrap()
+        );
+        assert!(expand("%(group_name)s", &variables).is_err());
+        assert!(expand("%(program_name)d", &variables).is_err());
+        assert!(expand("100%", &variables).is_err());
+    }
+
+    #[test]
+    fn parses_environment() {
+        let env = parse_environment("A=\"1,2\", B='x y',C=plain,\nD=").unwrap();
+        assert_eq!(
+            env,
+            BTreeMap::from([
+                ("A".to_string(), "1,2".to_string()),
+                ("B".to_string(), "x y".to_string()),
+                ("C".to_string(), "plain".to_string()),
+                ("D".to_string(), String::new()),
+            ])
+        );
+        assert!(parse_environment("A=\"1").is_err());
+        assert!(parse_environment("A=1,B").is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use super::{Job, status::JobStatus};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use tokio::sync::watch;
+
+/// A job that has to start before the job owning this dependency may start.
+#[derive(Debug)]
+pub struct Dependency {
+    pub name: String,
+    pub status: watch::Receiver<JobStatus>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+impl Job {
+    /// Adds missing dependencies of `jobs` and orders them so that every job comes after the
+    /// jobs it depends on. Fails if a dependency does not exist, is disabled, or if the
+    /// dependencies form a cycle.
+    pub fn resolve_dependencies(jobs: Vec<Self>, verbose: bool) -> Result<Vec<Self>> {
+        let mut selected: BTreeMap<String, Self> = jobs
+            .iter()
+            .map(|job| (job.name.clone(), job.clone()))
+            .collect();
+
+        let mut pending: Vec<Self> = jobs.clone();
+        while let Some(job) = pending.pop() {
+            for dependency in &job.depends_on {
+                if selected.contains_key(dependency) {
+                    continue;
+                }
+
+                let dependency_job = Self::load(dependency, verbose).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Job {} depends on {}, which does not exist.",
+                        job.name,
+                        dependency
+                    )
+                })?;
+                if !dependency_job.enabled {
+                    anyhow::bail!(
+                        "Job {} depends on {}, which is disabled.",
+                        job.name,
+                        dependency
+                    );
+                }
+
+                selected.insert(dependency.clone(), dependency_job.clone());
+                pending.push(dependency_job);
+            }
+        }
+
+        let mut visits = BTreeMap::new();
+        let mut order = vec![];
+        let mut path = vec![];
+        for job in jobs {
+            Self::visit(&job.name, &selected, &mut visits, &mut path, &mut order)?;
+        }
+
+        Ok(order
+            .into_iter()
+            .filter_map(|name| selected.remove(&name))
+            .collect())
+    }
+
+    fn visit(
+        name: &str,
+        jobs: &BTreeMap<String, Self>,
+        visits: &mut BTreeMap<String, Visit>,
+        path: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<()> {
+        match visits.get(name) {
+            Some(Visit::Done) => return Ok(()),
+            Some(Visit::InProgress) => {
+                let start = path.iter().position(|x| x == name).unwrap_or(0);
+                let mut cycle = path[start..].to_vec();
+                cycle.push(name.to_string());
+                anyhow::bail!("Dependency cycle detected: {}", cycle.join(" -> "));
+            }
+            None => (),
+        }
+
+        visits.insert(name.to_string(), Visit::InProgress);
+        path.push(name.to_string());
+
+        if let Some(job) = jobs.get(name) {
+            for dependency in &job.depends_on {
+                Self::visit(dependency, jobs, visits, path, order)?;
+            }
+        }
+
+        path.pop();
+        visits.insert(name.to_string(), Visit::Done);
+        order.push(name.to_string());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Job;
+    use std::path::PathBuf;
+
+    fn job(name: &str, depends_on: &[&str]) -> Job {
+        Job {
+            depends_on: depends_on.iter().map(ToString::to_string).collect(),
+            ..Job::new(
+                name.to_string(),
+                "true".to_string(),
+                vec![],
+                PathBuf::from("."),
+            )
+        }
+    }
+
+    fn names(jobs: &[Job]) -> Vec<&str> {
+        jobs.iter().map(|job| job.name.as_str()).collect()
+    }
+
+    #[test]
+    fn orders_jobs_after_their_dependencies() {
+        let jobs = vec![
+            job("api", &["db", "cache"]),
+            job("web", &["api"]),
+            job("db", &[]),
+            job("cache", &["db"]),
+        ];
+        let ordered = Job::resolve_dependencies(jobs, false).unwrap();
+        assert_eq!(names(&ordered), ["db", "cache", "api", "web"]);
+    }
+
+    #[test]
+    fn keeps_independent_jobs_in_order() {
+        let jobs = vec![job("b", &[]), job("a", &[])];
+        let ordered = Job::resolve_dependencies(jobs, false).unwrap();
+        assert_eq!(names(&ordered), ["b", "a"]);
+    }
+
+    #[test]
+    fn detects_cycles() {
+        let jobs = vec![job("a", &["b"]), job("b", &["c"]), job("c", &["a"])];
+        let error = Job::resolve_dependencies(jobs, false).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Dependency cycle detected: a -> b -> c -> a"
+        );
+    }
+
+    #[test]
+    fn detects_jobs_depending_on_themselves() {
+        let jobs = vec![job("a", &["a"])];
+        let error = Job::resolve_dependencies(jobs, false).unwrap_err();
+        assert_eq!(error.to_string(), "Dependency cycle detected: a -> a");
+    }
+}
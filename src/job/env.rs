@@ -0,0 +1,55 @@
+use super::Job;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+/// Parses a `KEY=VALUE` pair.
+pub fn parse_env_var(var: &str) -> Result<(String, String), String> {
+    let (key, value) = var
+        .split_once('=')
+        .ok_or_else(|| format!("invalid environment variable {var}, expected KEY=VALUE"))?;
+    if key.is_empty() {
+        return Err(format!("invalid environment variable {var}, key is empty"));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+impl Job {
+    /// Variables set for the job's process, in addition to the inherited environment. Values
+    /// from `env` take precedence over values from `env_file`.
+    pub fn environment(&self) -> Result<BTreeMap<String, String>> {
+        let mut environment = BTreeMap::new();
+
+        if let Some(env_file) = &self.env_file {
+            let path = self.working_directory.join(env_file);
+            let iter = dotenvy::from_path_iter(&path)
+                .with_context(|| format!("Could not read env file {}", path.display()))?;
+            for item in iter {
+                let (key, value) =
+                    item.with_context(|| format!("Invalid env file {}", path.display()))?;
+                environment.insert(key, value);
+            }
+        }
+
+        environment.extend(self.env.clone());
+        Ok(environment)
+    }
+
+    /// Applies `environment`, as returned by [`Job::environment`], to a command about to be
+    /// spawned, clearing the inherited environment first if the job asks for it.
+    pub fn apply_environment(
+        &self,
+        command: &mut tokio::process::Command,
+        environment: &BTreeMap<String, String>,
+    ) {
+        if self.clear_env {
+            command.env_clear();
+            for key in &self.env_allow {
+                if let Some(value) = std::env::var_os(key) {
+                    command.env(key, value);
+                }
+            }
+        }
+
+        command.envs(environment);
+    }
+}
@@ -1,14 +1,29 @@
 use crate::colors::Tend;
 use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    process::ExitStatus,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
 
-use super::Job;
+use super::{Job, filter::Filter, signal::Signal};
+use crate::supervisor::SupervisorCommand;
 
-#[derive(PartialEq, Eq)]
 pub enum ControlFlow<'a> {
     Nothing,
     RestartCommand(&'a str),
+    /// Restart asked for by the user or a hook of another job. It is not delayed and does not
+    /// count towards the restart limit.
+    RestartRequested,
     StopJob(&'a str),
+    /// Run the command of a hook next to the job.
+    Exec(&'a Hook, Trigger),
+    /// Ask the supervisor to act on other jobs.
+    Supervise(&'a Hook, SupervisorCommand),
+    /// Send a signal to the process and keep it running.
+    Signal(&'a Hook, Signal),
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize, clap::ValueEnum, Copy, PartialEq, Eq)]
@@ -20,6 +35,33 @@ pub enum RestartBehavior {
     Never,
 }
 
+impl RestartBehavior {
+    /// Exit hooks implementing this behavior. They apply when no exit hook of the job matches.
+    pub fn default_hooks(self) -> &'static [Hook] {
+        static HOOKS: LazyLock<[[Hook; 2]; 4]> = LazyLock::new(|| {
+            [
+                RestartBehavior::Always,
+                RestartBehavior::OnSuccess,
+                RestartBehavior::OnFailure,
+                RestartBehavior::Never,
+            ]
+            .map(|behavior| {
+                let (on_success, on_failure) = match behavior {
+                    RestartBehavior::Always => (Action::Restart, Action::Restart),
+                    RestartBehavior::OnSuccess => (Action::Restart, Action::Stop),
+                    RestartBehavior::OnFailure => (Action::Stop, Action::Restart),
+                    RestartBehavior::Never => (Action::Stop, Action::Stop),
+                };
+                [
+                    Hook::new("success".to_string(), Event::FinishedSuccess, on_success),
+                    Hook::new("failure".to_string(), Event::FinishedFailure, on_failure),
+                ]
+            })
+        });
+        &HOOKS[self as usize]
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, clap::ValueEnum, Copy, PartialEq, Eq)]
 pub enum RestartStrategy {
     Immediate,
@@ -39,7 +81,7 @@ impl RestartStrategy {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, ValueEnum, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, ValueEnum, Default, PartialEq, Eq)]
 pub enum Stream {
     Stdout,
     Stderr,
@@ -47,18 +89,211 @@ pub enum Stream {
     Any,
 }
 
-/// TODO: Rework [`Job::restart`] to use this instead of [`JobRestartStrategy`]
+impl Stream {
+    /// Whether lines read from `source` are seen when watching this stream.
+    pub const fn watches(&self, source: &Self) -> bool {
+        matches!(
+            (self, source),
+            (Self::Any, _) | (Self::Stdout, Self::Stdout) | (Self::Stderr, Self::Stderr)
+        )
+    }
+
+    /// Whether a `line` read from `source` contains `substring`, when watching this stream.
+    pub fn detect_substring(&self, source: &Self, line: &str, substring: &str) -> bool {
+        self.watches(source) && line.contains(substring)
+    }
+}
+
+/// Regular expression compiled once when the job is loaded, stored as its source text.
+#[derive(Clone)]
+pub struct Pattern(regex::Regex);
+
+impl std::fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0.as_str())
+    }
+}
+
+impl Pattern {
+    /// Groups captured by the pattern in `line`, or `None` if it does not match. Groups are
+    /// named by their index, or by their name if they have one.
+    pub fn captures(&self, line: &str) -> Option<Vec<(String, String)>> {
+        let captures = self.0.captures(line)?;
+        Some(
+            self.0
+                .capture_names()
+                .enumerate()
+                .skip(1)
+                .filter_map(|(index, name)| {
+                    let value = captures.get(index)?.as_str().to_string();
+                    Some((
+                        name.map_or_else(|| index.to_string(), str::to_string),
+                        value,
+                    ))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = regex::Error;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        regex::Regex::new(pattern).map(Self)
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        pattern.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, clap::Parser)]
 pub enum Event {
-    // FinishedSuccess,
-    // FinishedFailure,
-    DetectSubstring { stream: Stream, contains: String },
+    /// The process exited successfully.
+    FinishedSuccess,
+    /// The process exited unsuccessfully, or waiting for it failed.
+    FinishedFailure,
+    /// The process exited with one of `codes` or was killed by one of `signals`. Matches every
+    /// exit if both are empty.
+    Exit {
+        #[serde(default)]
+        #[arg(long)]
+        codes: Vec<i32>,
+        #[serde(default)]
+        #[arg(long)]
+        signals: Vec<Signal>,
+    },
+    DetectSubstring {
+        stream: Stream,
+        contains: String,
+    },
+    DetectRegex {
+        stream: Stream,
+        pattern: Pattern,
+    },
+    /// Nothing was printed on the stream for this many seconds.
+    Silence {
+        stream: Stream,
+        #[serde(deserialize_with = "deserialize_seconds")]
+        #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+        seconds: u64,
+    },
+}
+
+/// Rejects zero, which would make a silence hook match all the time.
+fn deserialize_seconds<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match u64::deserialize(deserializer)? {
+        0 => Err(serde::de::Error::custom("seconds must be at least 1")),
+        seconds => Ok(seconds),
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, ValueEnum)]
+impl Event {
+    /// Captured groups if the event matches a `line` read from `source`. Substrings capture
+    /// nothing.
+    pub fn detect(&self, source: &Stream, line: &str) -> Option<Vec<(String, String)>> {
+        match self {
+            Self::DetectSubstring { stream, contains } => stream
+                .detect_substring(source, line, contains)
+                .then(Vec::new),
+            Self::DetectRegex { stream, pattern } => {
+                stream.watches(source).then(|| pattern.captures(line))?
+            }
+            Self::FinishedSuccess
+            | Self::FinishedFailure
+            | Self::Exit { .. }
+            | Self::Silence { .. } => None,
+        }
+    }
+
+    /// Whether the event matches the process exiting with `status`, which is `None` if waiting
+    /// for the process failed.
+    pub fn detect_exit(&self, status: Option<&ExitStatus>) -> bool {
+        match self {
+            Self::FinishedSuccess => status.is_some_and(ExitStatus::success),
+            Self::FinishedFailure => !status.is_some_and(ExitStatus::success),
+            Self::Exit { codes, signals } => status.is_some_and(|status| {
+                (codes.is_empty() && signals.is_empty())
+                    || status.code().is_some_and(|code| codes.contains(&code))
+                    || signals.iter().any(|signal| signal.terminated(*status))
+            }),
+            Self::DetectSubstring { .. } | Self::DetectRegex { .. } | Self::Silence { .. } => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Action {
     Restart,
     Stop,
+    /// Runs a command in the job's working directory and environment without affecting the
+    /// job. The command is killed after `timeout_seconds`.
+    Exec {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default = "default_exec_timeout_seconds")]
+        timeout_seconds: u64,
+    },
+    /// Restarts a running job of the same `tend run` session or daemon.
+    RestartJob(String),
+    /// Stops a running job of the same session.
+    StopJob(String),
+    /// Starts a job in the same session, unless it is already running.
+    StartJob(String),
+    RestartGroup(String),
+    StopGroup(String),
+    StartGroup(String),
+    /// Sends a signal to the process without restarting it, for example SIGHUP to reload its
+    /// configuration.
+    Signal(Signal),
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Restart => f.write_str("restart"),
+            Self::Stop => f.write_str("stop"),
+            Self::Exec { program, .. } => write!(f, "run {program}"),
+            Self::RestartJob(job) => write!(f, "restart job {job}"),
+            Self::StopJob(job) => write!(f, "stop job {job}"),
+            Self::StartJob(job) => write!(f, "start job {job}"),
+            Self::RestartGroup(group) => write!(f, "restart group {group}"),
+            Self::StopGroup(group) => write!(f, "stop group {group}"),
+            Self::StartGroup(group) => write!(f, "start group {group}"),
+            Self::Signal(signal) => write!(f, "send {signal}"),
+        }
+    }
+}
+
+pub const fn default_exec_timeout_seconds() -> u64 {
+    60
+}
+
+/// What made a hook fire, passed to the command of an exec action.
+#[derive(Debug, Default)]
+pub struct Trigger {
+    pub stream: Option<Stream>,
+    pub line: Option<String>,
+    /// Groups captured by a regular expression.
+    pub captures: Vec<(String, String)>,
+    pub exit_status: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,62 +301,355 @@ pub struct Hook {
     pub name: String,
     pub event: Event,
     pub action: Action,
+    /// Fire only once the event happened this many times, within `within_seconds` if set.
+    #[serde(default)]
+    pub count: Option<usize>,
+    #[serde(default)]
+    pub within_seconds: Option<u64>,
+    /// Do not fire again for this many seconds after firing.
+    #[serde(default)]
+    pub cooldown_seconds: Option<u64>,
+}
+
+impl Hook {
+    /// Creates a hook that fires every time its event happens.
+    pub const fn new(name: String, event: Event, action: Action) -> Self {
+        Self {
+            name,
+            event,
+            action,
+            count: None,
+            within_seconds: None,
+            cooldown_seconds: None,
+        }
+    }
+
+    pub fn control_flow(&self, trigger: Trigger) -> ControlFlow<'_> {
+        let supervise = |command| ControlFlow::Supervise(self, command);
+        match &self.action {
+            Action::Restart => ControlFlow::RestartCommand(self.name.as_str()),
+            Action::Stop => ControlFlow::StopJob(self.name.as_str()),
+            Action::Exec { .. } => ControlFlow::Exec(self, trigger),
+            Action::RestartJob(job) => supervise(SupervisorCommand::Restart(Filter::job(job))),
+            Action::StopJob(job) => supervise(SupervisorCommand::Stop(Filter::job(job))),
+            Action::StartJob(job) => supervise(SupervisorCommand::Start(Filter::job(job))),
+            Action::RestartGroup(group) => {
+                supervise(SupervisorCommand::Restart(Filter::group(group)))
+            }
+            Action::StopGroup(group) => supervise(SupervisorCommand::Stop(Filter::group(group))),
+            Action::StartGroup(group) => supervise(SupervisorCommand::Start(Filter::group(group))),
+            Action::Signal(signal) => ControlFlow::Signal(self, *signal),
+        }
+    }
+}
+
+/// When the event of a hook happened, for thresholds and cooldowns.
+#[derive(Debug, Default, Clone)]
+struct HookMatches {
+    /// Matches counting towards the threshold, oldest first.
+    recent: VecDeque<Instant>,
+    last_matched: Option<Instant>,
+    last_fired: Option<Instant>,
+}
+
+/// State of the hooks of a running job, kept across restarts.
+#[derive(Debug)]
+pub struct HookState {
+    hooks: Vec<HookMatches>,
+}
+
+impl HookState {
+    pub fn new(job: &Job) -> Self {
+        Self {
+            hooks: vec![HookMatches::default(); job.event_hooks.len()],
+        }
+    }
+
+    /// Records that the event of `hook`, the job's hook at `index`, happened. Returns whether
+    /// the hook fires.
+    fn matched(&mut self, index: usize, hook: &Hook) -> bool {
+        let now = Instant::now();
+        let matches = &mut self.hooks[index];
+        matches.last_matched = Some(now);
+        matches.recent.push_back(now);
+        if let Some(within_seconds) = hook.within_seconds {
+            let window = Duration::from_secs(within_seconds);
+            while matches
+                .recent
+                .front()
+                .is_some_and(|matched| now.duration_since(*matched) > window)
+            {
+                matches.recent.pop_front();
+            }
+        }
+        let count = hook.count.unwrap_or(1);
+        while matches.recent.len() > count {
+            matches.recent.pop_front();
+        }
+        if matches.recent.len() < count {
+            return false;
+        }
+
+        if let (Some(cooldown_seconds), Some(last_fired)) =
+            (hook.cooldown_seconds, matches.last_fired)
+            && now.duration_since(last_fired) < Duration::from_secs(cooldown_seconds)
+        {
+            return false;
+        }
+
+        matches.last_fired = Some(now);
+        matches.recent.clear();
+        true
+    }
 }
 
 impl Job {
-    pub fn stdout_line_callback<'a>(&'a self, line: &str, verbose: bool) -> ControlFlow<'a> {
-        for hook in &self.event_hooks {
-            let Hook {
-                name,
-                event: Event::DetectSubstring { stream, contains },
-                action,
-            } = hook;
-
-            let detection = match stream {
-                Stream::Any | Stream::Stdout => line.contains(contains),
-                Stream::Stderr => false,
+    /// Runs the action of the first hook that fires because its event matches a `line` read
+    /// from `source`.
+    pub fn line_callback<'a>(
+        &'a self,
+        source: &Stream,
+        line: &str,
+        hooks: &mut HookState,
+        verbose: bool,
+    ) -> ControlFlow<'a> {
+        for (index, hook) in self.event_hooks.iter().enumerate() {
+            let Some(captures) = hook.event.detect(source, line) else {
+                continue;
             };
+            if !hooks.matched(index, hook) {
+                continue;
+            }
 
-            if detection {
-                if verbose {
-                    println!("{} triggered hook {:?}", self.name.job(), hook);
+            if verbose {
+                println!("{} triggered hook {:?}", self.name.job(), hook);
+                if !captures.is_empty() {
+                    let captures: Vec<String> = captures
+                        .iter()
+                        .map(|(name, value)| format!("{name}={value}"))
+                        .collect();
+                    println!("{} captured {}", self.name.job(), captures.join(", "));
                 }
-
-                return match action {
-                    Action::Restart => ControlFlow::RestartCommand(name),
-                    Action::Stop => ControlFlow::StopJob(name),
-                };
             }
+
+            return hook.control_flow(Trigger {
+                stream: Some(source.clone()),
+                line: Some(line.to_string()),
+                captures,
+                exit_status: None,
+            });
         }
 
         ControlFlow::Nothing
     }
 
-    pub fn stderr_line_callback<'a>(&'a self, line: &str, verbose: bool) -> ControlFlow<'a> {
-        for hook in &self.event_hooks {
-            let Hook {
-                name,
-                event: Event::DetectSubstring { stream, contains },
-                action,
-            } = hook;
+    /// The silence hook that matches first, its index and when it matches, given the last
+    /// time a line was read from each stream. A silence hook matches again only after another
+    /// period of silence.
+    pub fn next_silence(
+        &self,
+        last_stdout: Instant,
+        last_stderr: Instant,
+        hooks: &HookState,
+    ) -> Option<(Instant, usize)> {
+        self.event_hooks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, hook)| {
+                let Event::Silence { stream, seconds } = &hook.event else {
+                    return None;
+                };
+                let last_output = match stream {
+                    Stream::Stdout => last_stdout,
+                    Stream::Stderr => last_stderr,
+                    Stream::Any => last_stdout.max(last_stderr),
+                };
+                let since = hooks.hooks[index]
+                    .last_matched
+                    .map_or(last_output, |matched| matched.max(last_output));
+                Some((since + Duration::from_secs((*seconds).max(1)), index))
+            })
+            .min_by_key(|(deadline, _)| *deadline)
+    }
 
-            let detection = match stream {
-                Stream::Stdout => false,
-                Stream::Any | Stream::Stderr => line.contains(contains),
-            };
+    /// Records that the silence hook at `index` matched. Returns the hook if it fires.
+    pub fn silence_hook_fired<'a>(
+        &'a self,
+        index: usize,
+        hooks: &mut HookState,
+    ) -> Option<&'a Hook> {
+        let hook = &self.event_hooks[index];
+        hooks.matched(index, hook).then_some(hook)
+    }
 
-            if detection {
-                if verbose {
-                    println!("{} triggered hook {:?}", self.name.job(), hook);
-                }
+    /// Runs the actions of exit hooks that fire because they match how the process exited:
+    /// the actions of hooks that leave the job alone, then the first hook that restarts or
+    /// stops the job, falling back to the default hooks of the job's restart behavior.
+    pub fn exit_callback(
+        &self,
+        status: Option<&ExitStatus>,
+        hooks: &mut HookState,
+        verbose: bool,
+    ) -> Vec<ControlFlow<'_>> {
+        let trigger = || Trigger {
+            exit_status: status.map(ToString::to_string),
+            ..Trigger::default()
+        };
 
-                return match action {
-                    Action::Restart => ControlFlow::RestartCommand(name),
-                    Action::Stop => ControlFlow::StopJob(name),
-                };
+        let mut fired = vec![];
+        for (index, hook) in self.event_hooks.iter().enumerate() {
+            if !hook.event.detect_exit(status) || !hooks.matched(index, hook) {
+                continue;
+            }
+            if verbose {
+                println!("{} triggered hook {:?}", self.name.job(), hook);
+            }
+            let control = hook.control_flow(trigger());
+            let decides = matches!(
+                control,
+                ControlFlow::RestartCommand(_) | ControlFlow::StopJob(_)
+            );
+            fired.push(control);
+            if decides {
+                return fired;
             }
         }
 
-        ControlFlow::Nothing
+        if let Some(hook) = self
+            .restart
+            .default_hooks()
+            .iter()
+            .find(|hook| hook.event.detect_exit(status))
+        {
+            if verbose {
+                println!("{} triggered hook {:?}", self.name.job(), hook);
+            }
+            fired.push(hook.control_flow(trigger()));
+        }
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, ControlFlow, Event, Hook, HookState, Job, Stream};
+    use std::{
+        path::PathBuf,
+        time::{Duration, Instant},
+    };
+
+    fn job(event_hooks: Vec<Hook>) -> Job {
+        Job {
+            event_hooks,
+            ..Job::new(
+                "job".to_string(),
+                "true".to_string(),
+                vec![],
+                PathBuf::from("."),
+            )
+        }
+    }
+
+    fn hook(
+        count: Option<usize>,
+        within_seconds: Option<u64>,
+        cooldown_seconds: Option<u64>,
+    ) -> Hook {
+        Hook {
+            count,
+            within_seconds,
+            cooldown_seconds,
+            ..Hook::new("hook".to_string(), Event::FinishedFailure, Action::Stop)
+        }
+    }
+
+    fn ago(seconds: u64) -> Instant {
+        Instant::now()
+            .checked_sub(Duration::from_secs(seconds))
+            .unwrap()
+    }
+
+    #[test]
+    fn count_fires_on_the_last_match() {
+        let hook = hook(Some(3), None, None);
+        let mut state = HookState::new(&job(vec![hook.clone()]));
+        assert!(!state.matched(0, &hook));
+        assert!(!state.matched(0, &hook));
+        assert!(state.matched(0, &hook));
+        // Firing resets the count.
+        assert!(!state.matched(0, &hook));
+    }
+
+    #[test]
+    fn matches_outside_the_window_do_not_count() {
+        let hook = hook(Some(2), Some(10), None);
+        let mut state = HookState::new(&job(vec![hook.clone()]));
+        state.hooks[0].recent.push_back(ago(11));
+        assert!(!state.matched(0, &hook));
+
+        let mut state = HookState::new(&job(vec![hook.clone()]));
+        state.hooks[0].recent.push_back(ago(9));
+        assert!(state.matched(0, &hook));
+    }
+
+    #[test]
+    fn cooldown_blocks_firing_again() {
+        let hook = hook(None, None, Some(60));
+        let mut state = HookState::new(&job(vec![hook.clone()]));
+        assert!(state.matched(0, &hook));
+        assert!(!state.matched(0, &hook));
+
+        state.hooks[0].last_fired = Some(ago(61));
+        assert!(state.matched(0, &hook));
+    }
+
+    #[test]
+    fn silence_deadline_is_after_the_last_output() {
+        let silence = |seconds| {
+            Hook::new(
+                "silence".to_string(),
+                Event::Silence {
+                    stream: Stream::Any,
+                    seconds,
+                },
+                Action::Stop,
+            )
+        };
+        let job = job(vec![silence(0), silence(5)]);
+        let state = HookState::new(&job);
+        let since = Instant::now();
+        let (deadline, index) = job.next_silence(since, since, &state).unwrap();
+        assert!(deadline > since);
+        assert_eq!(index, 0);
+    }
+
+    #[test]
+    fn zero_silence_seconds_are_rejected() {
+        let event = |seconds| {
+            serde_json::from_str::<Event>(&format!(
+                r#"{{"Silence": {{"stream": "Any", "seconds": {seconds}}}}}"#
+            ))
+        };
+        assert!(event(0).is_err());
+        assert!(event(1).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn exit_hooks_that_do_not_fire_fall_through_to_default_hooks() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let job = job(vec![hook(Some(2), None, None)]);
+        let mut state = HookState::new(&job);
+        let failure = std::process::ExitStatus::from_raw(1 << 8);
+
+        let fired = job.exit_callback(Some(&failure), &mut state, false);
+        assert!(matches!(
+            fired[..],
+            [ControlFlow::RestartCommand("failure")]
+        ));
+
+        let fired = job.exit_callback(Some(&failure), &mut state, false);
+        assert!(matches!(fired[..], [ControlFlow::StopJob("hook")]));
     }
 }
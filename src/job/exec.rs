@@ -0,0 +1,107 @@
+use super::{
+    Job,
+    event::{Action, Hook, Stream, Trigger},
+};
+use crate::colors::Tend;
+use anyhow::Result;
+use std::time::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    task::JoinSet,
+};
+
+async fn print_lines(output: Option<impl AsyncRead + Unpin>, prefix: &str) {
+    let Some(output) = output else {
+        return;
+    };
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        println!("{prefix}{line}");
+    }
+}
+
+impl Job {
+    /// Starts the command of an exec hook in `commands` without waiting for it. What triggered
+    /// the hook is passed in `TEND_*` environment variables, and the output is printed with the
+    /// hook name in its prefix.
+    pub fn spawn_hook_command(
+        &self,
+        hook: &Hook,
+        trigger: &Trigger,
+        commands: &mut JoinSet<()>,
+    ) -> Result<()> {
+        let Action::Exec {
+            program,
+            args,
+            timeout_seconds,
+        } = &hook.action
+        else {
+            return Ok(());
+        };
+
+        let environment = self.environment()?;
+        let mut command = tokio::process::Command::new(program);
+        self.apply_environment(&mut command, &environment);
+        command
+            .args(args)
+            .current_dir(&self.working_directory)
+            .env("TEND_JOB", &self.name)
+            .env("TEND_HOOK", &hook.name)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(stream) = &trigger.stream {
+            let stream = match stream {
+                Stream::Stdout => "stdout",
+                Stream::Stderr => "stderr",
+                Stream::Any => "any",
+            };
+            command.env("TEND_STREAM", stream);
+        }
+        if let Some(line) = &trigger.line {
+            command.env("TEND_LINE", line);
+        }
+        if let Some(exit_status) = &trigger.exit_status {
+            command.env("TEND_EXIT_STATUS", exit_status);
+        }
+        for (name, value) in &trigger.captures {
+            command.env(format!("TEND_CAPTURE_{name}"), value);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Could not run {program}: {e}"))?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let prefix = format!(
+            "{} {} ",
+            self.name.job(),
+            format!("[{}]", hook.name).thick()
+        );
+        let program = program.clone();
+        let timeout = Duration::from_secs(*timeout_seconds);
+
+        while commands.try_join_next().is_some() {}
+        commands.spawn(async move {
+            let finished = tokio::time::timeout(timeout, async {
+                tokio::join!(print_lines(stdout, &prefix), print_lines(stderr, &prefix));
+                child.wait().await
+            })
+            .await;
+            match finished {
+                Ok(Ok(status)) if status.success() => (),
+                Ok(Ok(status)) => println!("{prefix}{} {} ({status})", program, "failed".failure()),
+                Ok(Err(e)) => println!("{prefix}{} {}: {e}", program, "failed".failure()),
+                Err(_) => println!(
+                    "{prefix}{} {} after {} seconds",
+                    program,
+                    "timed out".failure(),
+                    timeout.as_secs()
+                ),
+            }
+        });
+
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 use super::Job;
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Filter {
     All {
         exclude: Vec<String>,
@@ -12,6 +14,22 @@ pub enum Filter {
 }
 
 impl Filter {
+    pub fn job(name: &str) -> Self {
+        Self::Subset {
+            jobs: vec![name.to_string()],
+            groups: vec![],
+            exclude: vec![],
+        }
+    }
+
+    pub fn group(name: &str) -> Self {
+        Self::Subset {
+            jobs: vec![],
+            groups: vec![name.to_string()],
+            exclude: vec![],
+        }
+    }
+
     pub fn matches_name(&self, job_name: &str) -> bool {
         match self {
             Self::All { exclude } => !exclude.iter().any(|x| x == job_name),
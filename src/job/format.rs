@@ -0,0 +1,90 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::path::{Path, PathBuf};
+
+/// File format of a job definition, chosen by the extension of the job file. Files without an
+/// extension are JSON, as written by earlier versions of tend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    /// Extensions recognized for each format, in order of preference.
+    const EXTENSIONS: [(&str, Self); 4] = [
+        ("json", Self::Json),
+        ("toml", Self::Toml),
+        ("yaml", Self::Yaml),
+        ("yml", Self::Yaml),
+    ];
+
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+        }
+    }
+
+    /// Prefix of lines that are comments in this format.
+    pub const fn comment(self) -> &'static str {
+        match self {
+            Self::Json => "//",
+            Self::Toml | Self::Yaml => "#",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|extension| {
+                Self::EXTENSIONS
+                    .iter()
+                    .find(|(known, _)| extension == *known)
+                    .map(|(_, format)| *format)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Name of the job stored in a file of the jobs directory.
+    pub fn job_name(path: &Path) -> Option<String> {
+        let file_name = path.file_name()?.to_str()?;
+        Some(
+            Self::EXTENSIONS
+                .iter()
+                .find_map(|(extension, _)| file_name.strip_suffix(&format!(".{extension}")))
+                .unwrap_or(file_name)
+                .to_string(),
+        )
+    }
+
+    /// Files a job named `name` may be stored in, in order of preference.
+    pub fn candidates(jobs_dir: &Path, name: &str) -> Vec<PathBuf> {
+        std::iter::once(jobs_dir.join(name))
+            .chain(
+                Self::EXTENSIONS
+                    .iter()
+                    .map(|(extension, _)| jobs_dir.join(format!("{name}.{extension}"))),
+            )
+            .collect()
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String> {
+        Ok(match self {
+            Self::Json => serde_json::to_string_pretty(value)?,
+            Self::Toml => toml::to_string_pretty(value)?,
+            Self::Yaml => serde_yaml::to_string(value)?,
+        })
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        Ok(match self {
+            Self::Json => serde_json::from_str(content)?,
+            Self::Toml => toml::from_str(content)?,
+            Self::Yaml => serde_yaml::from_str(content)?,
+        })
+    }
+}
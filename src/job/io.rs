@@ -1,59 +1,165 @@
-use super::{Job, filter};
+use super::{Job, filter, format::Format, project::Project};
 use crate::colors::Tend;
+use crate::config::Config;
 use anyhow::Result;
 use prettytable::{Table, format, row};
 use std::path::PathBuf;
 
 impl Job {
     fn jobs_dir() -> Result<PathBuf> {
-        let home = dirs_next::home_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
-        let jobs = home.join(".tend").join("jobs");
+        let jobs = crate::paths::tend_dir()?.join("jobs");
         std::fs::create_dir_all(&jobs)?;
         Ok(jobs)
     }
 
+    /// Fails if the job was declared in a project file, which tend never writes to.
+    pub fn ensure_editable(&self) -> Result<()> {
+        if let Some(project) = &self.project {
+            anyhow::bail!(
+                "Job {} is declared in {}. Edit that file instead.",
+                self.name,
+                project.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the file a job is stored in, if it exists.
+    pub fn find_file(name: &str) -> Result<Option<PathBuf>> {
+        Ok(Format::candidates(&Self::jobs_dir()?, name)
+            .into_iter()
+            .find(|path| path.is_file()))
+    }
+
+    /// Saves the job in the format of its existing file, or in the configured default format
+    /// for new jobs.
     pub fn save(&self, overwrite: bool) -> Result<()> {
-        let jobs = Self::jobs_dir()?;
-        let file = std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(overwrite)
-            .create_new(!overwrite)
-            .open(jobs.join(&self.name))?;
-        // serde_json::to_writer(file, self)?;
-        serde_json::to_writer_pretty(file, self)?;
+        self.save_as(None, overwrite)
+    }
+
+    /// Saves the job in `format`, replacing its existing file if that has another format.
+    pub fn save_as(&self, format: Option<Format>, overwrite: bool) -> Result<()> {
+        self.ensure_editable()?;
+        let existing = Self::find_file(&self.name)?;
+        if existing.is_some() && !overwrite {
+            return Err(std::io::Error::from(std::io::ErrorKind::AlreadyExists).into());
+        }
+
+        let format = match (format, &existing) {
+            (Some(format), _) => format,
+            (None, Some(existing)) => Format::from_path(existing),
+            (None, None) => Config::load()?.job_format,
+        };
+        let path = match &existing {
+            Some(existing) if Format::from_path(existing) == format => existing.clone(),
+            _ => Self::jobs_dir()?.join(format!("{}.{}", self.name, format.extension())),
+        };
+
+        std::fs::write(&path, format.serialize(self)?)?;
+        if let Some(existing) = existing
+            && existing != path
+        {
+            std::fs::remove_file(existing)?;
+        }
 
         Ok(())
     }
 
     pub fn load(name: &str, verbose: bool) -> Option<Self> {
-        let jobs = Self::jobs_dir().ok()?;
-        let file = std::fs::File::open(jobs.join(name)).ok()?;
+        let mut job = Self::load_unresolved(name, verbose)?;
+        if let Some(template) = job.template {
+            job.apply_template(template);
+        }
+        Some(job)
+    }
 
-        let job: Result<Self, _> = serde_json::from_reader(file);
-        match job {
-            Ok(mut job) => {
-                if let Some(template) = job.template {
-                    job.apply_template(template);
-                }
+    /// Loads a job as it is stored on disk, without applying its template. Use this to edit
+    /// a job so that template hooks are not saved into the job file.
+    pub fn load_unresolved(name: &str, verbose: bool) -> Option<Self> {
+        let Some(path) = Self::find_file(name).ok()? else {
+            return Self::project_jobs(verbose)
+                .into_iter()
+                .find(|job| job.name == name);
+        };
 
-                Some(job)
-            }
+        let job = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Format::from_path(&path).deserialize(&content));
+        match job {
+            Ok(job) => Some(job),
             Err(e) => {
                 eprintln!("{} {}: {}", name.job(), "could not be loaded".failure(), e);
                 if verbose {
-                    eprintln!(
-                        "{} is located at: {}",
-                        name.job(),
-                        jobs.join(name).display()
-                    );
+                    eprintln!("{} is located at: {}", name.job(), path.display());
                 }
                 None
             }
         }
     }
 
+    /// Renames a saved job. Fails if a job with the new name already exists. Jobs depending on
+    /// this job and the job's logs are updated to use the new name.
+    pub fn rename(&mut self, new_name: &str, verbose: bool) -> Result<()> {
+        let format = Self::find_file(&self.name)?.map(|path| Format::from_path(&path));
+
+        // Move the logs first, so a failure leaves the job as it was.
+        let old_logs = super::log::JobLog::dir(&self.name)?;
+        let new_logs = super::log::JobLog::dir(new_name)?;
+        let move_logs = old_logs.exists();
+        if move_logs {
+            if new_logs.exists() {
+                anyhow::bail!(
+                    "Logs of {new_name} already exist in {}, remove them first.",
+                    new_logs.display()
+                );
+            }
+            std::fs::rename(&old_logs, &new_logs)?;
+        }
+
+        let old_name = std::mem::replace(&mut self.name, new_name.to_string());
+        if let Err(e) = self.save_as(format, false) {
+            self.name = old_name;
+            if move_logs {
+                let _ = std::fs::rename(&new_logs, &old_logs);
+            }
+            if e.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::AlreadyExists)
+            {
+                anyhow::bail!("Job {new_name} already exists.");
+            }
+            return Err(e);
+        }
+        Self::delete_unchecked(&old_name)?;
+
+        let mut dependents = vec![];
+        Self::iterate_jobs_filtered(
+            |job| {
+                if job.depends_on.contains(&old_name) {
+                    dependents.push(job.name);
+                }
+            },
+            &filter::Filter::All { exclude: vec![] },
+            true,
+            verbose,
+        )?;
+        for name in dependents {
+            let Some(mut job) = Self::load_unresolved(&name, verbose) else {
+                continue;
+            };
+            for dependency in &mut job.depends_on {
+                if *dependency == old_name {
+                    new_name.clone_into(dependency);
+                }
+            }
+            job.save(true)?;
+            if verbose {
+                println!("{} now depends on {}", name.job(), new_name.job());
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn delete_all_unchecked() -> Result<()> {
         let jobs = Self::jobs_dir()?;
         for entry in std::fs::read_dir(jobs)? {
@@ -68,16 +174,52 @@ impl Job {
     }
 
     pub fn delete_unchecked(name: &str) -> Result<()> {
-        let jobs = Self::jobs_dir()?;
-        std::fs::remove_file(jobs.join(name))?;
+        let path = Self::find_file(name)?.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Job {name} not found"),
+            )
+        })?;
+        std::fs::remove_file(path)?;
 
         Ok(())
     }
 
+    /// Names of the jobs stored in `~/.tend/jobs`, sorted.
+    fn stored_job_names() -> Result<Vec<String>> {
+        let mut names = std::collections::BTreeSet::new();
+        for entry in std::fs::read_dir(Self::jobs_dir()?)? {
+            let path = entry?.path();
+            if path.is_file()
+                && let Some(name) = Format::job_name(&path)
+            {
+                names.insert(name);
+            }
+        }
+
+        Ok(names.into_iter().collect())
+    }
+
     pub fn delete(&self) -> Result<()> {
+        self.ensure_editable()?;
         Self::delete_unchecked(&self.name)
     }
 
+    /// Jobs declared in the project file of the current directory, without their templates
+    /// applied.
+    fn project_jobs(verbose: bool) -> Vec<Self> {
+        match Project::current() {
+            Ok(project) => project.map(|project| project.jobs).unwrap_or_default(),
+            Err(e) => {
+                eprintln!("{} {:#}", "Project could not be loaded:".failure(), e);
+                if verbose {
+                    eprintln!("Only jobs from ~/.tend/jobs are available.");
+                }
+                vec![]
+            }
+        }
+    }
+
     pub fn iterate_job_names_filtered<F>(
         mut f: F,
         filter: &filter::Filter,
@@ -86,17 +228,9 @@ impl Job {
     where
         F: FnMut(&str),
     {
-        let jobs = Self::jobs_dir()?;
-        for entry in std::fs::read_dir(jobs)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_file() {
-                let Some(job_name) = path.file_name().unwrap().to_str() else {
-                    continue;
-                };
-                if filter.matches_name(job_name) {
-                    f(job_name);
-                }
+        for job_name in Self::stored_job_names()? {
+            if filter.matches_name(&job_name) {
+                f(&job_name);
             }
         }
 
@@ -112,24 +246,32 @@ impl Job {
     where
         F: FnMut(Self),
     {
-        let jobs = Self::jobs_dir()?;
-        for entry in std::fs::read_dir(jobs)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_file() {
-                let name = path.file_name().unwrap().to_str().unwrap();
-                let Some(job) = Self::load(name, verbose) else {
-                    continue;
-                };
+        for name in Self::stored_job_names()? {
+            let Some(job) = Self::load(&name, verbose) else {
+                continue;
+            };
 
-                if !include_disabled && !job.enabled {
-                    continue;
-                }
+            if !include_disabled && !job.enabled {
+                continue;
+            }
 
-                if !filter.matches(&job) {
-                    continue;
-                }
+            if !filter.matches(&job) {
+                continue;
+            }
+
+            f(job);
+        }
+
+        for mut job in Self::project_jobs(verbose) {
+            if let Some(template) = job.template {
+                job.apply_template(template);
+            }
+
+            if !include_disabled && !job.enabled {
+                continue;
+            }
 
+            if filter.matches(&job) {
                 f(job);
             }
         }
@@ -138,7 +280,6 @@ impl Job {
     }
 
     pub fn list(job_filter: &filter::Filter, verbose: bool) -> Result<()> {
-        let jobs = Self::jobs_dir()?;
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_CLEAN);
 
@@ -152,19 +293,8 @@ impl Job {
             "GROUP",
         ]);
 
-        for entry in std::fs::read_dir(jobs)? {
-            let entry = entry?;
-            let path = entry.path();
-            if path.is_file() {
-                let name = path.file_name().unwrap().to_str().unwrap();
-                let Some(job) = Self::load(name, verbose) else {
-                    continue;
-                };
-
-                if !job_filter.matches(&job) {
-                    continue;
-                }
-
+        Self::iterate_jobs_filtered(
+            |job| {
                 table.add_row(row![
                     r->if job.enabled { "*" } else { " " },
                     bFC->&job.name,
@@ -174,8 +304,11 @@ impl Job {
                     job.restart_behaviour(),
                     job.group,
                 ]);
-            }
-        }
+            },
+            job_filter,
+            true,
+            verbose,
+        )?;
 
         if table.is_empty() {
             println!("No jobs found");
@@ -0,0 +1,375 @@
+use super::Job;
+use crate::colors::Tend;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+use tokio::sync::mpsc::{self, UnboundedSender};
+
+const CURRENT_LOG: &str = "output.log";
+
+/// Persistent log settings of a job.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LogConfig {
+    /// Size in bytes after which the current log file is rotated.
+    #[serde(default = "default_max_size")]
+    pub max_size: u64,
+    /// Number of rotated log files to keep.
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// Compress rotated log files with gzip.
+    #[serde(default = "super::return_true")]
+    pub compress: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            max_size: default_max_size(),
+            max_files: default_max_files(),
+            compress: true,
+        }
+    }
+}
+
+const fn default_max_size() -> u64 {
+    10 * 1024 * 1024
+}
+
+const fn default_max_files() -> usize {
+    5
+}
+
+/// Parses sizes such as `512`, `64K`, `10M` or `1G`.
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let (number, multiplier) = match size.chars().last().map(|c| c.to_ascii_uppercase()) {
+        Some('K') => (&size[..size.len() - 1], 1024),
+        Some('M') => (&size[..size.len() - 1], 1024 * 1024),
+        Some('G') => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("invalid size {size}: {e}"))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size {size} is too large"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+    /// Lifecycle events written by tend itself.
+    Tend,
+}
+
+impl LogStream {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+            Self::Tend => "tend",
+        }
+    }
+
+    pub fn parse(stream: &str) -> Option<Self> {
+        match stream {
+            "stdout" => Some(Self::Stdout),
+            "stderr" => Some(Self::Stderr),
+            "tend" => Some(Self::Tend),
+            _ => None,
+        }
+    }
+}
+
+/// A single line read back from a job log.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub stream: LogStream,
+    pub line: String,
+}
+
+impl LogEntry {
+    pub fn parse(entry: &str) -> Option<Self> {
+        let (timestamp, rest) = entry.split_once(' ')?;
+        let (stream, line) = rest.split_once(' ').unwrap_or((rest, ""));
+        Some(Self {
+            timestamp: chrono::DateTime::parse_from_rfc3339(timestamp)
+                .ok()?
+                .with_timezone(&chrono::Utc),
+            stream: LogStream::parse(stream)?,
+            line: line.to_string(),
+        })
+    }
+
+    /// Parses every complete line of `content`.
+    pub fn parse_all(content: &str) -> Vec<Self> {
+        content.lines().filter_map(Self::parse).collect()
+    }
+}
+
+/// Output of a job written to `~/.tend/logs/<job>/`. Lines are written and the log rotated by
+/// a blocking task, so slow disks or compression never hold up the job.
+#[derive(Debug)]
+pub struct JobLog {
+    tx: UnboundedSender<String>,
+}
+
+impl JobLog {
+    pub fn dir(job_name: &str) -> Result<PathBuf> {
+        Ok(crate::paths::logs_dir()?.join(job_name))
+    }
+
+    /// Opens the log of `job`. Returns `None` if the job does not keep logs.
+    pub fn open(job: &Job) -> Result<Option<Self>> {
+        let Some(config) = job.log.clone() else {
+            return Ok(None);
+        };
+
+        let file = LogFile::open(Self::dir(&job.name)?, config)?;
+        Ok(Some(Self::spawn(job.name.clone(), file)))
+    }
+
+    /// Starts writing to `file`. The task stops once every line has been written after the log
+    /// is dropped, or after the first error.
+    fn spawn(job_name: String, mut file: LogFile) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        tokio::task::spawn_blocking(move || {
+            while let Some(entry) = rx.blocking_recv() {
+                if let Err(e) = file.write(&entry) {
+                    eprintln!(
+                        "{} {}: {}",
+                        job_name.job(),
+                        "could not write log".failure(),
+                        e
+                    );
+                    break;
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    pub fn current_path(job_name: &str) -> Result<PathBuf> {
+        Ok(Self::dir(job_name)?.join(CURRENT_LOG))
+    }
+
+    /// Returns the rotated log files of a job, oldest first. The current log is not included.
+    pub fn rotated_files(job_name: &str) -> Result<Vec<PathBuf>> {
+        let dir = Self::dir(job_name)?;
+        let mut files = vec![];
+        for index in 1.. {
+            let compressed = rotated_path(&dir, index, true);
+            let plain = rotated_path(&dir, index, false);
+            if compressed.exists() {
+                files.push(compressed);
+            } else if plain.exists() {
+                files.push(plain);
+            } else {
+                break;
+            }
+        }
+        files.reverse();
+        Ok(files)
+    }
+
+    /// Reads a log file, decompressing it if needed.
+    pub fn read_file(path: &Path) -> Result<String> {
+        let mut content = String::new();
+        let file = File::open(path)?;
+        if path.extension().is_some_and(|extension| extension == "gz") {
+            flate2::read::GzDecoder::new(file).read_to_string(&mut content)?;
+        } else {
+            std::io::BufReader::new(file).read_to_string(&mut content)?;
+        }
+        Ok(content)
+    }
+
+    /// Queues a line to be written. Lines are dropped once writing failed, which is reported
+    /// by the writing task.
+    pub fn write(&self, stream: LogStream, line: &str) {
+        let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        let _ = self
+            .tx
+            .send(format!("{timestamp} {} {line}\n", stream.as_str()));
+    }
+}
+
+fn rotated_path(dir: &Path, index: usize, compressed: bool) -> PathBuf {
+    if compressed {
+        dir.join(format!("output.{index}.log.gz"))
+    } else {
+        dir.join(format!("output.{index}.log"))
+    }
+}
+
+/// The current log file of a job, written synchronously.
+#[derive(Debug)]
+struct LogFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    config: LogConfig,
+}
+
+impl LogFile {
+    fn open(dir: PathBuf, config: LogConfig) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let file = Self::open_current(&dir)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            dir,
+            file,
+            size,
+            config,
+        })
+    }
+
+    fn open_current(dir: &Path) -> Result<File> {
+        Ok(std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(CURRENT_LOG))?)
+    }
+
+    fn write(&mut self, entry: &str) -> Result<()> {
+        self.file.write_all(entry.as_bytes())?;
+        self.size += entry.len() as u64;
+
+        if self.size >= self.config.max_size {
+            self.rotate()?;
+        }
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let current = self.dir.join(CURRENT_LOG);
+
+        if self.config.max_files == 0 {
+            self.file = File::create(&current)?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        for compressed in [false, true] {
+            let oldest = rotated_path(&self.dir, self.config.max_files, compressed);
+            if oldest.exists() {
+                std::fs::remove_file(oldest)?;
+            }
+        }
+
+        for index in (1..self.config.max_files).rev() {
+            for compressed in [false, true] {
+                let from = rotated_path(&self.dir, index, compressed);
+                if from.exists() {
+                    std::fs::rename(from, rotated_path(&self.dir, index + 1, compressed))?;
+                }
+            }
+        }
+
+        let rotated = rotated_path(&self.dir, 1, false);
+        std::fs::rename(&current, &rotated)?;
+        self.file = Self::open_current(&self.dir)?;
+        self.size = 0;
+
+        if self.config.compress {
+            let mut input = File::open(&rotated)?;
+            let output = File::create(rotated_path(&self.dir, 1, true))?;
+            let mut encoder = flate2::write::GzEncoder::new(output, flate2::Compression::default());
+            std::io::copy(&mut input, &mut encoder)?;
+            encoder.finish()?;
+            std::fs::remove_file(rotated)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JobLog, LogConfig, LogFile, LogStream, parse_size, rotated_path};
+    use std::path::Path;
+
+    fn config(max_files: usize, compress: bool) -> LogConfig {
+        LogConfig {
+            max_size: 10,
+            max_files,
+            compress,
+        }
+    }
+
+    #[test]
+    fn rotates_and_compresses_full_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = LogFile::open(dir.path().to_path_buf(), config(2, true)).unwrap();
+        for entry in ["first line\n", "second line\n", "third line\n", "fourth\n"] {
+            file.write(entry).unwrap();
+        }
+
+        let read = |path: &Path| JobLog::read_file(path).unwrap();
+        assert_eq!(read(&rotated_path(dir.path(), 2, true)), "second line\n");
+        assert_eq!(read(&rotated_path(dir.path(), 1, true)), "third line\n");
+        assert!(!rotated_path(dir.path(), 3, true).exists());
+        assert_eq!(read(&dir.path().join("output.log")), "fourth\n");
+    }
+
+    #[test]
+    fn truncates_logs_without_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = LogFile::open(dir.path().to_path_buf(), config(0, false)).unwrap();
+        file.write("first line\n").unwrap();
+        file.write("second\n").unwrap();
+        assert!(!rotated_path(dir.path(), 1, false).exists());
+        assert_eq!(
+            JobLog::read_file(&dir.path().join("output.log")).unwrap(),
+            "second\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn writes_on_a_current_thread_runtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = LogFile::open(dir.path().to_path_buf(), config(2, false)).unwrap();
+        let log = JobLog::spawn("job".to_string(), file);
+        log.write(LogStream::Stdout, "a line long enough to rotate");
+        log.write(LogStream::Tend, "done");
+        drop(log);
+
+        let read = |path: &Path| JobLog::read_file(path).unwrap_or_default();
+        let last = rotated_path(dir.path(), 1, false);
+        for _ in 0..100 {
+            if read(&last).ends_with(" tend done\n") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(read(&last).ends_with(" tend done\n"));
+        let first = read(&rotated_path(dir.path(), 2, false));
+        assert!(first.ends_with(" stdout a line long enough to rotate\n"));
+    }
+
+    #[test]
+    fn parses_sizes_with_units() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("64k"), Ok(64 * 1024));
+        assert_eq!(parse_size("10M"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Ok(1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn rejects_sizes_that_overflow() {
+        assert!(parse_size("18446744073709551615K").is_err());
+        assert!(parse_size("17179869184G").is_err());
+        assert_eq!(
+            parse_size("17179869183G"),
+            Ok(17_179_869_183 * 1024 * 1024 * 1024)
+        );
+    }
+}
@@ -1,21 +1,36 @@
+pub mod dependency;
+pub mod env;
 pub mod event;
+pub mod exec;
 pub mod filter;
+pub mod format;
 pub mod io;
+pub mod log;
+pub mod probe;
+pub mod project;
 pub mod run;
+pub mod signal;
+pub mod status;
 pub mod template;
 
 use crate::{colors::Tend, job::event::ControlFlow};
 use anyhow::Result;
 use folktime::Folktime;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 use tokio::{
     io::{AsyncBufReadExt, BufReader, Lines},
     process::{ChildStderr, ChildStdout},
-    sync::mpsc::Receiver,
+    sync::{
+        mpsc::{Receiver, UnboundedSender},
+        watch,
+    },
 };
 
-use self::event::{Hook, RestartBehavior, RestartStrategy};
+use self::{
+    event::{Hook, RestartBehavior, RestartStrategy},
+    status::JobStatus,
+};
 
 #[allow(clippy::unsafe_derive_deserialize)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,24 +46,74 @@ pub struct Job {
     pub restart: RestartBehavior,
     #[serde(default)]
     pub restart_strategy: RestartStrategy,
+    /// Stop restarting after this many restarts in a row. Running for ten minutes resets the
+    /// count.
+    #[serde(default)]
+    pub max_restarts: Option<u64>,
+    /// Signal asking the process to stop, followed by SIGKILL if it is still running after
+    /// `stop_timeout_seconds`. Windows processes are always killed right away.
+    #[serde(default)]
+    pub stop_signal: signal::Signal,
+    #[serde(default = "default_stop_timeout_seconds")]
+    pub stop_timeout_seconds: u64,
     #[serde(default)]
     pub event_hooks: Vec<Hook>,
     #[serde(default)]
     pub template: Option<template::Template>,
+    #[serde(default)]
+    pub log: Option<log::LogConfig>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub readiness: Option<probe::Readiness>,
+    #[serde(default)]
+    pub liveness: Vec<probe::LivenessCheck>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Dotenv file read before each start, relative to the working directory.
+    #[serde(default)]
+    pub env_file: Option<PathBuf>,
+    /// Start the job with an empty environment except for the variables in `env_allow`.
+    #[serde(default)]
+    pub clear_env: bool,
+    #[serde(default)]
+    pub env_allow: Vec<String>,
+    /// Project file the job was declared in. `None` for jobs stored in `~/.tend/jobs`.
+    #[serde(skip)]
+    pub project: Option<PathBuf>,
 }
 
 impl Job {
-    pub const fn restart_on_success(&self) -> bool {
-        match self.restart {
-            RestartBehavior::Always | RestartBehavior::OnSuccess => true,
-            RestartBehavior::OnFailure | RestartBehavior::Never => false,
-        }
-    }
-
-    pub const fn restart_on_failure(&self) -> bool {
-        match self.restart {
-            RestartBehavior::Always | RestartBehavior::OnFailure => true,
-            RestartBehavior::OnSuccess | RestartBehavior::Never => false,
+    /// Creates an enabled job in the default group with default settings.
+    pub fn new(
+        name: String,
+        program: String,
+        args: Vec<String>,
+        working_directory: PathBuf,
+    ) -> Self {
+        Self {
+            name,
+            enabled: true,
+            group: "default".to_string(),
+            program,
+            args,
+            working_directory,
+            restart: RestartBehavior::default(),
+            restart_strategy: RestartStrategy::default(),
+            max_restarts: None,
+            stop_signal: signal::Signal::default(),
+            stop_timeout_seconds: default_stop_timeout_seconds(),
+            event_hooks: vec![],
+            template: None,
+            log: None,
+            depends_on: vec![],
+            readiness: None,
+            liveness: vec![],
+            env: BTreeMap::new(),
+            env_file: None,
+            clear_env: false,
+            env_allow: vec![],
+            project: None,
         }
     }
 
@@ -62,6 +127,40 @@ impl Job {
     }
 }
 
+/// Commands sent by the supervisor to a running job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobCommand {
+    Stop,
+    Restart,
+}
+
+/// Channels connecting a running job to its supervisor.
+#[derive(Debug)]
+pub struct JobContext {
+    pub rx: Receiver<JobCommand>,
+    pub status: watch::Sender<JobStatus>,
+    pub log: Option<log::JobLog>,
+    pub dependencies: Vec<dependency::Dependency>,
+    pub verbose: bool,
+    /// Commands of hooks acting on other jobs.
+    pub supervisor: UnboundedSender<crate::supervisor::SupervisorCommand>,
+    /// Commands started by exec hooks, waited for before the job stops.
+    pub hook_commands: tokio::task::JoinSet<()>,
+}
+
+impl JobContext {
+    /// Appends a line to the job's log, if it keeps one.
+    pub fn log(&self, stream: log::LogStream, line: &str) {
+        if let Some(log) = &self.log {
+            log.write(stream, line);
+        }
+    }
+}
+
 const fn return_true() -> bool {
     true
 }
+
+pub const fn default_stop_timeout_seconds() -> u64 {
+    10
+}
@@ -0,0 +1,338 @@
+use super::{Job, event::Stream};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+/// Conditions that mark a running job as ready. The job is ready once any probe passes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Readiness {
+    pub probes: Vec<ReadinessProbe>,
+    /// Seconds between attempts of the TCP and exec probes.
+    #[serde(default = "default_interval_seconds")]
+    pub interval_seconds: u64,
+    /// Seconds after which a single attempt of a TCP or exec probe fails.
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self {
+            probes: vec![],
+            interval_seconds: default_interval_seconds(),
+            timeout_seconds: default_timeout_seconds(),
+        }
+    }
+}
+
+const fn default_interval_seconds() -> u64 {
+    1
+}
+
+const fn default_timeout_seconds() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ReadinessProbe {
+    /// The job printed a line containing a substring.
+    LogLine { stream: Stream, contains: String },
+    /// A TCP connection to a localhost port succeeds.
+    Tcp { port: u16 },
+    /// A command exits successfully.
+    Exec { program: String, args: Vec<String> },
+}
+
+impl std::fmt::Display for ReadinessProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LogLine { contains, .. } => write!(f, "log line \"{contains}\""),
+            Self::Tcp { port } => write!(f, "tcp port {port}"),
+            Self::Exec { program, args } => write!(f, "exec {program} {}", args.join(" ")),
+        }
+    }
+}
+
+impl ReadinessProbe {
+    /// Runs a single attempt of a TCP or exec probe. Log line probes are never polled.
+    async fn poll(&self, working_directory: &Path, timeout: Duration) -> bool {
+        match self {
+            Self::LogLine { .. } => false,
+            Self::Tcp { port } => tcp_check(*port, timeout).await,
+            Self::Exec { program, args } => {
+                exec_check(program, args, working_directory, timeout).await
+            }
+        }
+    }
+}
+
+/// Periodic check of a running job. The job is restarted once the check fails
+/// `failure_threshold` times in a row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LivenessCheck {
+    pub probe: LivenessProbe,
+    #[serde(default = "default_liveness_interval_seconds")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+pub const fn default_liveness_interval_seconds() -> u64 {
+    10
+}
+
+pub const fn default_failure_threshold() -> u32 {
+    3
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LivenessProbe {
+    /// A TCP connection to a localhost port succeeds.
+    Tcp { port: u16 },
+    /// An HTTP GET request to a localhost URL returns a 2xx or 3xx status.
+    Http { url: String },
+    /// A command exits successfully.
+    Exec { program: String, args: Vec<String> },
+}
+
+impl std::fmt::Display for LivenessProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tcp { port } => write!(f, "tcp port {port}"),
+            Self::Http { url } => write!(f, "http {url}"),
+            Self::Exec { program, args } => write!(f, "exec {program} {}", args.join(" ")),
+        }
+    }
+}
+
+impl std::fmt::Display for LivenessCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (every {}s, timeout {}s, {} failures)",
+            self.probe, self.interval_seconds, self.timeout_seconds, self.failure_threshold
+        )
+    }
+}
+
+impl LivenessProbe {
+    async fn check(&self, working_directory: &Path, timeout: Duration) -> bool {
+        match self {
+            Self::Tcp { port } => tcp_check(*port, timeout).await,
+            Self::Http { url } => http_check(url, timeout).await,
+            Self::Exec { program, args } => {
+                exec_check(program, args, working_directory, timeout).await
+            }
+        }
+    }
+}
+
+/// Splits a `http://host[:port][/path]` URL whose host is the local machine into its
+/// host, port and path.
+fn split_local_url(url: &str) -> Result<(&str, u16, &str), String> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("invalid url {url}: only http:// urls are supported"))?;
+    let (authority, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.ends_with(']') => (
+            host,
+            port.parse()
+                .map_err(|e| format!("invalid port in url {url}: {e}"))?,
+        ),
+        _ => (authority, 80),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if !matches!(host, "localhost" | "127.0.0.1" | "::1") {
+        return Err(format!("invalid url {url}: host must be localhost"));
+    }
+    Ok((host, port, path))
+}
+
+/// Validates an URL for an HTTP liveness check.
+pub fn parse_local_url(url: &str) -> Result<String, String> {
+    split_local_url(url)?;
+    Ok(url.to_string())
+}
+
+pub async fn http_check(url: &str, timeout: Duration) -> bool {
+    matches!(
+        tokio::time::timeout(timeout, http_get(url)).await,
+        Ok(Ok(status)) if (200..400).contains(&status)
+    )
+}
+
+/// Sends a GET request and returns the response status code.
+async fn http_get(url: &str) -> anyhow::Result<u16> {
+    let (host, port, path) = split_local_url(url).map_err(anyhow::Error::msg)?;
+    let mut stream = tokio::net::TcpStream::connect((host, port)).await?;
+    let request = format!("GET {path} HTTP/1.0\r\nHost: {host}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut status_line = String::new();
+    tokio::io::BufReader::new(stream)
+        .read_line(&mut status_line)
+        .await?;
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid HTTP response: {status_line}"))
+}
+
+pub async fn tcp_check(port: u16, timeout: Duration) -> bool {
+    matches!(
+        tokio::time::timeout(timeout, tokio::net::TcpStream::connect(("localhost", port))).await,
+        Ok(Ok(_))
+    )
+}
+
+pub async fn exec_check(
+    program: &str,
+    args: &[String],
+    working_directory: &Path,
+    timeout: Duration,
+) -> bool {
+    let child = tokio::process::Command::new(program)
+        .args(args)
+        .current_dir(working_directory)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let Ok(mut child) = child else {
+        return false;
+    };
+
+    matches!(
+        tokio::time::timeout(timeout, child.wait()).await,
+        Ok(Ok(status)) if status.success()
+    )
+}
+
+#[derive(Debug)]
+pub enum ProbeEvent {
+    Ready(String),
+    /// A liveness check reached its failure threshold.
+    Unhealthy(String),
+}
+
+/// Probes running in the background for a single process of a job. Dropping this stops them.
+#[derive(Debug)]
+pub struct Probes {
+    events: mpsc::Receiver<ProbeEvent>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for Probes {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl Probes {
+    pub fn spawn(job: &Job) -> Self {
+        let (tx, events) = mpsc::channel(1);
+        let mut tasks = vec![];
+
+        if let Some(readiness) = &job.readiness {
+            let polled: Vec<ReadinessProbe> = readiness
+                .probes
+                .iter()
+                .filter(|probe| !matches!(probe, ReadinessProbe::LogLine { .. }))
+                .cloned()
+                .collect();
+            if !polled.is_empty() {
+                tasks.push(tokio::spawn(poll_readiness(
+                    polled,
+                    job.working_directory.clone(),
+                    Duration::from_secs(readiness.interval_seconds.max(1)),
+                    Duration::from_secs(readiness.timeout_seconds),
+                    tx.clone(),
+                )));
+            }
+        }
+
+        for check in &job.liveness {
+            tasks.push(tokio::spawn(poll_liveness(
+                check.clone(),
+                job.working_directory.clone(),
+                tx.clone(),
+            )));
+        }
+
+        Self { events, tasks }
+    }
+
+    /// Waits for the next event. Returns `None` once no probes are left running.
+    pub async fn next(&mut self) -> Option<ProbeEvent> {
+        self.events.recv().await
+    }
+}
+
+async fn poll_readiness(
+    probes: Vec<ReadinessProbe>,
+    working_directory: PathBuf,
+    interval: Duration,
+    timeout: Duration,
+    tx: mpsc::Sender<ProbeEvent>,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        for probe in &probes {
+            if probe.poll(&working_directory, timeout).await {
+                let _ = tx.send(ProbeEvent::Ready(probe.to_string())).await;
+                return;
+            }
+        }
+    }
+}
+
+async fn poll_liveness(
+    check: LivenessCheck,
+    working_directory: PathBuf,
+    tx: mpsc::Sender<ProbeEvent>,
+) {
+    let period = Duration::from_secs(check.interval_seconds.max(1));
+    let timeout = Duration::from_secs(check.timeout_seconds);
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    let mut failures = 0;
+    loop {
+        interval.tick().await;
+        if check.probe.check(&working_directory, timeout).await {
+            failures = 0;
+            continue;
+        }
+
+        failures += 1;
+        if failures >= check.failure_threshold {
+            let _ = tx
+                .send(ProbeEvent::Unhealthy(check.probe.to_string()))
+                .await;
+            return;
+        }
+    }
+}
+
+impl Job {
+    /// Returns the log line probe matched by a `line` read from `source`, if any.
+    pub fn log_line_readiness(&self, source: &Stream, line: &str) -> Option<&ReadinessProbe> {
+        self.readiness.as_ref()?.probes.iter().find(|probe| {
+            matches!(probe, ReadinessProbe::LogLine { stream, contains }
+                if stream.detect_substring(source, line, contains))
+        })
+    }
+}
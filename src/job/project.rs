@@ -0,0 +1,106 @@
+use super::Job;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// File names of project files, in order of preference.
+pub const PROJECT_FILES: [&str; 2] = ["Tendfile.toml", "tend.json"];
+
+/// Jobs declared in a `Tendfile.toml` or `tend.json` of a project. Their names are prefixed
+/// with the project name, e.g. `myapp/postgres`, and they belong to the group of the project
+/// name unless they declare another group.
+#[derive(Debug)]
+pub struct Project {
+    pub jobs: Vec<Job>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProjectSection {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectFile {
+    #[serde(default)]
+    project: ProjectSection,
+    #[serde(default)]
+    jobs: BTreeMap<String, serde_json::Map<String, serde_json::Value>>,
+}
+
+impl Project {
+    /// Finds the nearest project file in `dir` or its ancestors.
+    pub fn discover(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors().find_map(|dir| {
+            PROJECT_FILES
+                .iter()
+                .map(|file| dir.join(file))
+                .find(|path| path.is_file())
+        })
+    }
+
+    /// Loads the project of the current directory, if there is one.
+    pub fn current() -> Result<Option<Self>> {
+        let Some(path) = Self::discover(&std::env::current_dir()?) else {
+            return Ok(None);
+        };
+        Self::load(&path).map(Some)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read {}", path.display()))?;
+        let file: ProjectFile = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?
+        } else {
+            serde_json::from_str(&content).with_context(|| format!("Invalid {}", path.display()))?
+        };
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let name = file.project.name.unwrap_or_else(|| {
+            dir.file_name().map_or_else(
+                || "project".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            )
+        });
+
+        let mut jobs = vec![];
+        for (job_name, mut fields) in file.jobs {
+            fields.insert("name".to_string(), format!("{name}/{job_name}").into());
+            fields.entry("group").or_insert_with(|| name.clone().into());
+            fields
+                .entry("args")
+                .or_insert_with(|| serde_json::Value::Array(vec![]));
+            fields
+                .entry("working_directory")
+                .or_insert_with(|| ".".into());
+
+            let mut job: Job = serde_json::from_value(fields.into())
+                .with_context(|| format!("Invalid job {job_name} in {}", path.display()))?;
+            job.working_directory = dir.join(&job.working_directory);
+            if let Ok(working_directory) = job.working_directory.canonicalize() {
+                job.working_directory = working_directory;
+            }
+            job.project = Some(path.to_path_buf());
+            jobs.push(job);
+        }
+
+        // Dependencies refer to jobs of the same project unless no such job exists.
+        let job_names: Vec<String> = jobs.iter().map(|job| job.name.clone()).collect();
+        for job in &mut jobs {
+            for dependency in &mut job.depends_on {
+                let namespaced = format!("{name}/{dependency}");
+                if job_names.contains(&namespaced) {
+                    *dependency = namespaced;
+                }
+            }
+        }
+
+        Ok(Self { jobs })
+    }
+}
@@ -1,10 +1,29 @@
 use super::{
-    AsyncBufReadExt, BufReader, ChildStderr, ChildStdout, ControlFlow, Folktime, Job, Lines,
-    Receiver, Result, Tend,
+    AsyncBufReadExt, BufReader, ChildStderr, ChildStdout, ControlFlow, Folktime, Job, JobCommand,
+    JobContext, Lines, Result, Tend,
+    event::{Event, HookState, Stream, Trigger},
+    log::LogStream,
+    probe::{ProbeEvent, Probes},
+    status::JobState,
 };
 
 use process_wrap::tokio::{TokioChildWrapper, TokioCommandWrap};
 
+/// A spawned process of a job together with its output streams and probes.
+struct RunningProcess {
+    process: Box<dyn TokioChildWrapper>,
+    stdout: Lines<BufReader<ChildStdout>>,
+    stderr: Lines<BufReader<ChildStderr>>,
+    start_time: std::time::Instant,
+    /// Process group led by the process, which outlives it while its children run.
+    #[cfg(unix)]
+    pgid: Option<u32>,
+    /// When a line was last read from stdout and stderr, for silence hooks.
+    last_stdout: std::time::Instant,
+    last_stderr: std::time::Instant,
+    probes: Probes,
+}
+
 impl Job {
     fn duration(start_time: std::time::Instant) -> std::time::Duration {
         let end_time = std::time::Instant::now();
@@ -13,86 +32,301 @@ impl Job {
 
     async fn wait_for_something<'a>(
         &'a self,
-        process: &mut Box<dyn TokioChildWrapper>,
-        rx: &mut Receiver<()>,
-        verbose: bool,
-        stdout: &mut Lines<BufReader<ChildStdout>>,
-        stderr: &mut Lines<BufReader<ChildStderr>>,
-        start_time: std::time::Instant,
+        running: &mut RunningProcess,
+        context: &mut JobContext,
+        hooks: &mut HookState,
     ) -> Result<ControlFlow<'a>> {
-        let process: &mut tokio::process::Child = process.inner_mut();
+        let process: &mut tokio::process::Child = running.process.inner_mut();
+        let start_time = running.start_time;
+        let verbose = context.verbose;
+        let silence = self.next_silence(running.last_stdout, running.last_stderr, hooks);
 
         tokio::select! {
-            stdout_line = stdout.next_line() => {
+            stdout_line = running.stdout.next_line() => {
                 if let Some(line) = stdout_line? {
+                    running.last_stdout = std::time::Instant::now();
                     if verbose {
                         println!("{}{}{}{}", self.name.job(), " (stdout)".thick(), ": ".job(), line);
                     }else {
                         println!("{}{}", format!("{}: ", self.name).job(), line);
                     }
-                    return Ok(self.stdout_line_callback(&line, verbose));
+                    context.log(LogStream::Stdout, &line);
+                    if let Some(probe) = self.log_line_readiness(&Stream::Stdout, &line) {
+                        self.mark_ready(context, &probe.to_string());
+                    }
+                    return Ok(self.hook_fired(self.line_callback(&Stream::Stdout, &line, hooks, verbose), context));
                 }
                 Ok(ControlFlow::Nothing)
             }
-            stderr_line = stderr.next_line() => {
+            stderr_line = running.stderr.next_line() => {
                 if let Some(line) = stderr_line? {
+                    running.last_stderr = std::time::Instant::now();
                     println!("{}{}{}{}", self.name.job(), " (stderr)".failure(), ": ".job(), line);
-                    return Ok(self.stderr_line_callback(&line, verbose));
+                    context.log(LogStream::Stderr, &line);
+                    if let Some(probe) = self.log_line_readiness(&Stream::Stderr, &line) {
+                        self.mark_ready(context, &probe.to_string());
+                    }
+                    return Ok(self.hook_fired(self.line_callback(&Stream::Stderr, &line, hooks, verbose), context));
                 }
                 Ok(ControlFlow::Nothing)
             }
             a = process.wait() => {
-                if let Ok(status) = a {
-                    if status.success() {
+                let status = a.ok();
+                if let Some(status) = status {
+                    context.status.send_modify(|job_status| {
+                        job_status.pid = None;
+                        job_status.last_exit = Some(status.to_string());
+                    });
+                    context.log(LogStream::Tend, &format!("process exited ({status})"));
+                }
+                if status.is_some_and(|status| status.success()) {
+                    println!(
+                        "{} process finished indicating {} after running for {}",
+                        self.name.job(),
+                        "success".success(),
+                        Folktime::duration(Self::duration(start_time)).to_string().time_value(),
+                    );
+                } else {
+                    println!(
+                        "{} process finished indicating {}",
+                        self.name.job(),
+                        "failure".failure(),
+                    );
+                }
+                let mut control = ControlFlow::Nothing;
+                for fired in self.exit_callback(status.as_ref(), hooks, verbose) {
+                    control = self.hook_fired(fired, context);
+                }
+                Ok(control)
+            }
+            () = tokio::time::sleep_until(
+                silence.map_or_else(std::time::Instant::now, |(deadline, _)| deadline).into()
+            ), if silence.is_some() => {
+                Ok(silence.map_or(ControlFlow::Nothing, |(_, index)| self.silence_matched(index, hooks, context)))
+            }
+            Some(event) = running.probes.next() => {
+                match event {
+                    ProbeEvent::Ready(probe) => {
+                        self.mark_ready(context, &probe);
+                        Ok(ControlFlow::Nothing)
+                    }
+                    ProbeEvent::Unhealthy(probe) => {
                         println!(
-                            "{} process finished indicating {} after running for {}",
+                            "{} liveness check {} failed",
                             self.name.job(),
-                            "success".success(),
-                    Folktime::duration(Self::duration(start_time)).to_string().time_value(),
+                            probe.failure()
                         );
-                        return if self.restart_on_success() {
-                            Ok(ControlFlow::RestartCommand("success"))
-                        } else {
-                            Ok(ControlFlow::StopJob("success"))
-                        };
+                        context.log(LogStream::Tend, &format!("liveness check failed ({probe})"));
+                        Ok(ControlFlow::RestartCommand("liveness check failed"))
+                    }
+                }
+            }
+            command = context.rx.recv() => {
+                if command == Some(JobCommand::Restart) {
+                    if verbose {
+                        println!("{} received restart request", self.name.job());
                     }
+                    return Ok(ControlFlow::RestartRequested);
+                }
+
+                if verbose {
+                    println!("{} received termination signal", self.name.job());
                 }
+                Ok(ControlFlow::StopJob("termination signal"))
+            }
+        }
+    }
+
+    fn mark_ready(&self, context: &JobContext, probe: &str) {
+        if context.status.borrow().ready {
+            return;
+        }
+
+        context.status.send_modify(|status| status.ready = true);
+        context.log(LogStream::Tend, &format!("ready ({probe})"));
+        if context.verbose {
+            println!("{} ready ({})", self.name.job(), probe);
+        }
+    }
 
+    /// Records that the silence hook at `index` matched and returns its action if it fires.
+    fn silence_matched<'a>(
+        &'a self,
+        index: usize,
+        hooks: &mut HookState,
+        context: &mut JobContext,
+    ) -> ControlFlow<'a> {
+        let Some(hook) = self.silence_hook_fired(index, hooks) else {
+            return ControlFlow::Nothing;
+        };
+        if let Event::Silence { stream, seconds } = &hook.event {
+            let stream = match stream {
+                Stream::Stdout => " on stdout",
+                Stream::Stderr => " on stderr",
+                Stream::Any => "",
+            };
+            println!(
+                "{} {}",
+                self.name.job(),
+                format!("printed nothing{stream} for {seconds} seconds").failure()
+            );
+            context.log(
+                LogStream::Tend,
+                &format!("printed nothing{stream} for {seconds} seconds"),
+            );
+        }
+        if context.verbose {
+            println!("{} triggered hook {:?}", self.name.job(), hook);
+        }
+        self.hook_fired(hook.control_flow(Trigger::default()), context)
+    }
+
+    fn hook_fired<'a>(
+        &self,
+        control: ControlFlow<'a>,
+        context: &mut JobContext,
+    ) -> ControlFlow<'a> {
+        match control {
+            ControlFlow::RestartCommand(hook) | ControlFlow::StopJob(hook) => {
+                context
+                    .status
+                    .send_modify(|status| status.last_hook = Some(hook.to_string()));
+                control
+            }
+            ControlFlow::Exec(hook, trigger) => {
+                context
+                    .status
+                    .send_modify(|status| status.last_hook = Some(hook.name.clone()));
+                context.log(
+                    LogStream::Tend,
+                    &format!("running command of hook {}", hook.name),
+                );
+                if let Err(e) = self.spawn_hook_command(hook, &trigger, &mut context.hook_commands)
+                {
+                    eprintln!(
+                        "{} {} {}: {}",
+                        self.name.job(),
+                        "hook".failure(),
+                        hook.name,
+                        e
+                    );
+                }
+                ControlFlow::Nothing
+            }
+            ControlFlow::Supervise(hook, command) => {
+                context
+                    .status
+                    .send_modify(|status| status.last_hook = Some(hook.name.clone()));
                 println!(
-                    "{} process finished indicating {}",
+                    "{} hook {}: {}",
                     self.name.job(),
-                    "failure".failure(),
+                    hook.name.thick(),
+                    hook.action
                 );
-                if self.restart_on_failure() {
-                    Ok(ControlFlow::RestartCommand("failure"))
-                } else {
-                    Ok(ControlFlow::StopJob("failure"))
-                }
+                context.log(
+                    LogStream::Tend,
+                    &format!("hook {}: {}", hook.name, hook.action),
+                );
+                // The supervisor only goes away once every job has stopped.
+                let _ = context.supervisor.send(command);
+                ControlFlow::Nothing
             }
-            _ = rx.recv() => {
-                if verbose {
-                    println!("{} received termination signal", self.name.job());
+            ControlFlow::Signal(hook, signal) => {
+                context
+                    .status
+                    .send_modify(|status| status.last_hook = Some(hook.name.clone()));
+                context.log(
+                    LogStream::Tend,
+                    &format!("hook {}: sending {}", hook.name, signal),
+                );
+                control
+            }
+            ControlFlow::Nothing | ControlFlow::RestartRequested => control,
+        }
+    }
+
+    /// Waits out a restart delay. A restart request skips the rest of the delay, a stop
+    /// request ends it and returns `true`.
+    async fn back_off(delay_seconds: u64, context: &mut JobContext) -> bool {
+        tokio::select! {
+            () = tokio::time::sleep(tokio::time::Duration::from_secs(delay_seconds)) => false,
+            command = context.rx.recv() => command != Some(JobCommand::Restart),
+        }
+    }
+
+    /// Waits until every dependency is ready. Returns `false` if the job should not start,
+    /// either because it was asked to stop or because a dependency stopped before becoming ready.
+    async fn wait_for_dependencies(&self, context: &mut JobContext) -> bool {
+        if context.dependencies.is_empty() {
+            return true;
+        }
+
+        context
+            .status
+            .send_modify(|status| status.state = JobState::Waiting);
+
+        for dependency in &mut context.dependencies {
+            if context.verbose {
+                println!(
+                    "{} waiting for {} to become ready",
+                    self.name.job(),
+                    dependency.name.job()
+                );
+            }
+
+            while !dependency.status.borrow_and_update().ready {
+                tokio::select! {
+                    changed = dependency.status.changed() => {
+                        if changed.is_err() && !dependency.status.borrow().ready {
+                            println!(
+                                "{} not starting, dependency {} stopped",
+                                self.name.job(),
+                                dependency.name.job()
+                            );
+                            return false;
+                        }
+                    }
+                    command = context.rx.recv() => {
+                        if command != Some(JobCommand::Restart) {
+                            return false;
+                        }
+                    }
                 }
-                let _ = process.kill().await;
-                Ok(ControlFlow::StopJob("termination signal"))
             }
         }
+
+        true
     }
 
-    pub async fn create_repeated_process(self, mut rx: Receiver<()>, verbose: bool) -> Result<()> {
+    #[allow(clippy::too_many_lines)]
+    pub async fn create_repeated_process(self, mut context: JobContext) -> Result<()> {
+        let verbose = context.verbose;
         let mut backoff_restart_count = 0;
+        let mut hooks = HookState::new(&self);
+
+        if !self.wait_for_dependencies(&mut context).await {
+            context
+                .status
+                .send_modify(|status| status.state = JobState::Stopped);
+            return Ok(());
+        }
 
         'job: loop {
+            let environment = self.environment()?;
             let mut command = TokioCommandWrap::with_new(&self.program, |command| {
+                self.apply_environment(command, &environment);
                 command
                     .current_dir(&self.working_directory)
                     .args(&self.args)
                     .stdout(std::process::Stdio::piped())
                     .stderr(std::process::Stdio::piped());
             });
-            // NOTE: this actually made the subprocesses detach when tested on Ubuntu :D
-            //#[cfg(unix)]
-            //{ command.wrap(process_wrap::tokio::ProcessGroup::leader()); }
+            // Processes started by the program share its process group and are stopped with
+            // it. Ctrl-C in the terminal no longer reaches them directly, tend stops them.
+            #[cfg(unix)]
+            {
+                command.wrap(process_wrap::tokio::ProcessGroup::leader());
+            }
             #[cfg(windows)]
             {
                 command.wrap(process_wrap::tokio::JobObject);
@@ -104,8 +338,25 @@ impl Job {
                 println!("{} starting", self.name.job(),);
             }
             let start_time = std::time::Instant::now();
+            context.status.send_modify(|status| {
+                status.state = JobState::Running;
+                status.pid = process.id();
+                #[cfg(unix)]
+                {
+                    status.process_group = process.id();
+                }
+                status.started_at = Some(std::time::SystemTime::now());
+                status.restart_count = backoff_restart_count;
+                status.ready = false;
+            });
+            if let Some(pid) = process.id() {
+                context.log(LogStream::Tend, &format!("started (pid {pid})"));
+            }
+            if self.readiness.is_none() {
+                self.mark_ready(&context, "started");
+            }
 
-            let mut stdout = BufReader::new(
+            let stdout = BufReader::new(
                 process
                     .stdout()
                     .take()
@@ -113,7 +364,7 @@ impl Job {
             )
             .lines();
 
-            let mut stderr = BufReader::new(
+            let stderr = BufReader::new(
                 process
                     .stderr()
                     .take()
@@ -121,19 +372,33 @@ impl Job {
             )
             .lines();
 
+            let mut running = RunningProcess {
+                #[cfg(unix)]
+                pgid: process.id(),
+                process,
+                stdout,
+                stderr,
+                start_time,
+                last_stdout: start_time,
+                last_stderr: start_time,
+                probes: Probes::spawn(&self),
+            };
+
             loop {
                 let control = self
-                    .wait_for_something(
-                        &mut process,
-                        &mut rx,
-                        verbose,
-                        &mut stdout,
-                        &mut stderr,
-                        start_time,
-                    )
+                    .wait_for_something(&mut running, &mut context, &mut hooks)
                     .await?;
 
-                if control == ControlFlow::Nothing {
+                if let ControlFlow::Signal(hook, signal) = control {
+                    if verbose {
+                        println!("{} sending {} ({})", self.name.job(), signal, hook.name);
+                    }
+                    if let Err(e) = signal.send(running.process.as_ref()) {
+                        eprintln!("{} failed to send {}: {}", self.name.job(), signal, e);
+                    }
+                    continue;
+                }
+                if matches!(control, ControlFlow::Nothing) {
                     continue;
                 }
 
@@ -142,24 +407,64 @@ impl Job {
                     backoff_restart_count = 0;
                 }
 
+                let control = match control {
+                    ControlFlow::RestartCommand(reason)
+                        if self
+                            .max_restarts
+                            .is_some_and(|max| backoff_restart_count >= max) =>
+                    {
+                        println!(
+                            "{} {} after {} restarts in a row ({})",
+                            self.name.job(),
+                            "giving up".failure(),
+                            backoff_restart_count,
+                            reason,
+                        );
+                        ControlFlow::StopJob("restart limit reached")
+                    }
+                    control => control,
+                };
+
                 match control {
-                    ControlFlow::Nothing => (),
+                    ControlFlow::Nothing
+                    | ControlFlow::Exec(..)
+                    | ControlFlow::Supervise(..)
+                    | ControlFlow::Signal(..) => (),
+                    ControlFlow::RestartRequested => {
+                        context.log(LogStream::Tend, "restarting (restart requested)");
+                        println!("{} restarting (restart requested)", self.name.job());
+                        self.terminate_process(&mut running, &context).await?;
+                        continue 'job;
+                    }
                     ControlFlow::RestartCommand(reason) => {
+                        context.log(LogStream::Tend, &format!("restarting ({reason})"));
                         let delay_seconds =
                             self.restart_strategy.delay_seconds(backoff_restart_count);
                         if delay_seconds != 0 {
+                            context
+                                .status
+                                .send_modify(|status| status.state = JobState::BackingOff);
                             println!(
                                 "{} restarting in {} seconds ({})",
                                 self.name.job(),
                                 delay_seconds.to_string().time_value(),
                                 reason,
                             );
-                            tokio::time::sleep(tokio::time::Duration::from_secs(delay_seconds))
-                                .await;
-                            self.terminate_process(&mut process, verbose).await?;
+                            let stop_requested = Self::back_off(delay_seconds, &mut context).await;
+                            self.terminate_process(&mut running, &context).await?;
+
+                            if stop_requested {
+                                if verbose {
+                                    println!("{} stopping (termination signal)", self.name.job());
+                                }
+                                context
+                                    .status
+                                    .send_modify(|status| status.state = JobState::Stopped);
+                                break 'job;
+                            }
                         } else {
                             println!("{} restarting ({})", self.name.job(), reason);
-                            self.terminate_process(&mut process, verbose).await?;
+                            self.terminate_process(&mut running, &context).await?;
                         }
 
                         backoff_restart_count += 1;
@@ -167,40 +472,133 @@ impl Job {
                         continue 'job;
                     }
                     ControlFlow::StopJob(reason) => {
+                        context.log(LogStream::Tend, &format!("stopping ({reason})"));
                         if verbose {
                             println!("{} stopping ({})", self.name.job(), reason);
                         } else {
                             println!();
                         }
-                        self.terminate_process(&mut process, verbose).await?;
+                        self.terminate_process(&mut running, &context).await?;
+                        context
+                            .status
+                            .send_modify(|status| status.state = JobState::Stopped);
                         break 'job;
                     }
                 }
             }
         }
 
+        while context.hook_commands.join_next().await.is_some() {}
+
         Ok(())
     }
 
-    async fn terminate_process(
+    /// Sends the stop signal to the process group and waits for the whole group to exit within
+    /// the stop timeout. Returns `None` if the group has to be killed.
+    #[cfg(unix)]
+    async fn stop_gracefully(
         &self,
-        process: &mut Box<dyn TokioChildWrapper>,
+        running: &mut RunningProcess,
         verbose: bool,
-    ) -> Result<()> {
+    ) -> Option<std::process::ExitStatus> {
+        if self.stop_signal == super::signal::Signal::Kill {
+            return None;
+        }
+        if let Err(e) = self.stop_signal.send(running.process.as_ref()) {
+            eprintln!(
+                "{} failed to send {}: {}",
+                self.name.job(),
+                self.stop_signal,
+                e
+            );
+            return None;
+        }
+
         if verbose {
-            println!("{} terminating process", self.name.job());
+            println!("{} waiting for process to terminate", self.name.job());
         }
+        let timeout = std::time::Duration::from_secs(self.stop_timeout_seconds);
+        let Ok(exit_status) = tokio::time::timeout(timeout, async {
+            let exit_status = Box::into_pin(running.process.wait()).await;
+            Self::wait_for_group(running.pgid).await;
+            exit_status
+        })
+        .await
+        else {
+            println!(
+                "{} still running {} seconds after {}, {}",
+                self.name.job(),
+                self.stop_timeout_seconds.to_string().time_value(),
+                self.stop_signal,
+                "killing it".failure()
+            );
+            return None;
+        };
+        exit_status.ok()
+    }
 
-        if let Err(e) = process.start_kill() {
-            eprintln!("{} failed to send SIGTERM: {}", self.name.job(), e);
+    /// Windows processes have no stop signal and are killed right away.
+    #[cfg(windows)]
+    #[allow(clippy::unused_async)]
+    async fn stop_gracefully(
+        &self,
+        _running: &mut RunningProcess,
+        _verbose: bool,
+    ) -> Option<std::process::ExitStatus> {
+        None
+    }
+
+    /// Waits until no process is left in the process group, which includes children of the
+    /// process that outlived it.
+    #[cfg(unix)]
+    async fn wait_for_group(pgid: Option<u32>) {
+        let Some(pgid) = pgid else {
+            return;
+        };
+        while super::signal::group_alive(pgid) {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
+    }
 
+    async fn terminate_process(
+        &self,
+        running: &mut RunningProcess,
+        context: &JobContext,
+    ) -> Result<()> {
+        let verbose = context.verbose;
         if verbose {
-            println!("{} waiting for process to terminate", self.name.job());
+            println!("{} terminating process", self.name.job());
         }
 
-        let wait_future = Box::into_pin(process.wait());
-        wait_future.await?;
+        let exit_status = if let Some(exit_status) = self.stop_gracefully(running, verbose).await {
+            exit_status
+        } else {
+            if let Err(e) = super::signal::kill(running.process.as_mut()) {
+                eprintln!("{} failed to kill process: {}", self.name.job(), e);
+            }
+            let exit_status = Box::into_pin(running.process.wait()).await?;
+            // SIGKILL cannot be ignored, only processes that are not reaped yet can be left.
+            #[cfg(unix)]
+            if tokio::time::timeout(
+                std::time::Duration::from_secs(5),
+                Self::wait_for_group(running.pgid),
+            )
+            .await
+            .is_err()
+            {
+                eprintln!(
+                    "{} {} in its process group after killing it",
+                    self.name.job(),
+                    "processes are still running".failure()
+                );
+            }
+            exit_status
+        };
+        context.status.send_modify(|status| {
+            status.pid = None;
+            status.process_group = None;
+            status.last_exit = Some(exit_status.to_string());
+        });
 
         Ok(())
     }
@@ -0,0 +1,136 @@
+use clap::ValueEnum;
+use process_wrap::tokio::TokioChildWrapper;
+use serde::{Deserialize, Serialize};
+
+/// Unix signal, written by its name such as `SIGTERM`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Signal {
+    #[serde(rename = "SIGHUP")]
+    #[value(name = "SIGHUP", alias = "HUP")]
+    Hup,
+    #[serde(rename = "SIGINT")]
+    #[value(name = "SIGINT", alias = "INT")]
+    Int,
+    #[serde(rename = "SIGQUIT")]
+    #[value(name = "SIGQUIT", alias = "QUIT")]
+    Quit,
+    #[serde(rename = "SIGABRT")]
+    #[value(name = "SIGABRT", alias = "ABRT")]
+    Abrt,
+    #[serde(rename = "SIGKILL")]
+    #[value(name = "SIGKILL", alias = "KILL")]
+    Kill,
+    #[serde(rename = "SIGUSR1")]
+    #[value(name = "SIGUSR1", alias = "USR1")]
+    Usr1,
+    #[serde(rename = "SIGSEGV")]
+    #[value(name = "SIGSEGV", alias = "SEGV")]
+    Segv,
+    #[serde(rename = "SIGUSR2")]
+    #[value(name = "SIGUSR2", alias = "USR2")]
+    Usr2,
+    #[serde(rename = "SIGPIPE")]
+    #[value(name = "SIGPIPE", alias = "PIPE")]
+    Pipe,
+    #[serde(rename = "SIGALRM")]
+    #[value(name = "SIGALRM", alias = "ALRM")]
+    Alrm,
+    #[serde(rename = "SIGTERM")]
+    #[value(name = "SIGTERM", alias = "TERM")]
+    #[default]
+    Term,
+}
+
+impl Signal {
+    #[cfg(unix)]
+    pub const fn to_nix(self) -> nix::sys::signal::Signal {
+        use nix::sys::signal::Signal;
+        match self {
+            Self::Hup => Signal::SIGHUP,
+            Self::Int => Signal::SIGINT,
+            Self::Quit => Signal::SIGQUIT,
+            Self::Abrt => Signal::SIGABRT,
+            Self::Kill => Signal::SIGKILL,
+            Self::Usr1 => Signal::SIGUSR1,
+            Self::Segv => Signal::SIGSEGV,
+            Self::Usr2 => Signal::SIGUSR2,
+            Self::Pipe => Signal::SIGPIPE,
+            Self::Alrm => Signal::SIGALRM,
+            Self::Term => Signal::SIGTERM,
+        }
+    }
+
+    /// Sends the signal to the process group of the process. A group without processes left
+    /// is not an error.
+    #[cfg(unix)]
+    pub fn send(self, process: &dyn TokioChildWrapper) -> std::io::Result<()> {
+        match process.signal(self.to_nix() as i32) {
+            Err(e) if e.raw_os_error() == Some(nix::errno::Errno::ESRCH as i32) => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Fails, as there are no signals on Windows.
+    #[cfg(windows)]
+    pub fn send(self, _process: &dyn TokioChildWrapper) -> std::io::Result<()> {
+        Err(std::io::Error::other(format!(
+            "cannot send {self} on Windows"
+        )))
+    }
+
+    /// Whether the process was killed by this signal. Always `false` on Windows.
+    #[cfg(unix)]
+    pub fn terminated(self, status: std::process::ExitStatus) -> bool {
+        use std::os::unix::process::ExitStatusExt;
+        status.signal() == Some(self.to_nix() as i32)
+    }
+
+    /// Whether the process was killed by this signal. Always `false` on Windows.
+    #[cfg(windows)]
+    pub const fn terminated(self, _status: std::process::ExitStatus) -> bool {
+        false
+    }
+}
+
+/// Kills the process together with the rest of its process group on Unix, or its job object
+/// on Windows.
+#[cfg(unix)]
+pub fn kill(process: &dyn TokioChildWrapper) -> std::io::Result<()> {
+    Signal::Kill.send(process)
+}
+
+/// Kills the process together with the rest of its process group on Unix, or its job object
+/// on Windows.
+#[cfg(windows)]
+pub fn kill(process: &mut dyn TokioChildWrapper) -> std::io::Result<()> {
+    process.start_kill()
+}
+
+/// Kills every process left in the process group `pgid`.
+#[cfg(unix)]
+pub fn kill_group(pgid: u32) {
+    if let Ok(pgid) = i32::try_from(pgid) {
+        let _ = nix::sys::signal::killpg(
+            nix::unistd::Pid::from_raw(pgid),
+            nix::sys::signal::Signal::SIGKILL,
+        );
+    }
+}
+
+/// Whether any process is left in the process group `pgid`, including ones whose parent
+/// exited.
+#[cfg(unix)]
+pub fn group_alive(pgid: u32) -> bool {
+    let Ok(pgid) = i32::try_from(pgid) else {
+        return false;
+    };
+    nix::sys::signal::killpg(nix::unistd::Pid::from_raw(pgid), None)
+        != Err(nix::errno::Errno::ESRCH)
+}
+
+impl std::fmt::Display for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.to_possible_value().ok_or(std::fmt::Error)?;
+        f.write_str(value.get_name())
+    }
+}
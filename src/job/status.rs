@@ -0,0 +1,129 @@
+#[cfg(unix)]
+use crate::colors::Tend;
+#[cfg(unix)]
+use folktime::Folktime;
+#[cfg(unix)]
+use prettytable::{Table, format, row};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum JobState {
+    Starting,
+    Waiting,
+    Running,
+    BackingOff,
+    Stopped,
+}
+
+#[cfg(unix)]
+impl JobState {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Starting => "starting",
+            Self::Waiting => "waiting",
+            Self::Running => "running",
+            Self::BackingOff => "backing off",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+/// Live state of a job, published by its task while it runs under a supervisor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub group: String,
+    pub state: JobState,
+    pub pid: Option<u32>,
+    pub started_at: Option<SystemTime>,
+    pub restart_count: u64,
+    /// Whether the current process passed a readiness probe. Stays set after the process exits.
+    pub ready: bool,
+    pub last_exit: Option<String>,
+    pub last_hook: Option<String>,
+    /// Process group of the job's processes on Unix, until it is known to be empty.
+    #[serde(skip)]
+    pub process_group: Option<u32>,
+}
+
+impl JobStatus {
+    pub const fn new(name: String, group: String) -> Self {
+        Self {
+            name,
+            group,
+            state: JobState::Starting,
+            pid: None,
+            started_at: None,
+            restart_count: 0,
+            ready: false,
+            last_exit: None,
+            last_hook: None,
+            process_group: None,
+        }
+    }
+}
+
+/// Printing statuses for `tend status`, which asks the daemon and so only exists on Unix.
+#[cfg(unix)]
+impl JobStatus {
+    fn uptime(&self) -> Option<std::time::Duration> {
+        if self.state != JobState::Running {
+            return None;
+        }
+        SystemTime::now().duration_since(self.started_at?).ok()
+    }
+
+    pub fn print_table(statuses: &[Self]) {
+        if statuses.is_empty() {
+            println!("No jobs found");
+            return;
+        }
+
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_CLEAN);
+
+        table.set_titles(row![FB =>
+            "JOB",
+            "STATE",
+            "READY",
+            "PID",
+            "UPTIME",
+            "RESTARTS",
+            "LAST EXIT",
+            "LAST HOOK",
+            "GROUP",
+        ]);
+
+        for status in statuses {
+            let state = match status.state {
+                JobState::Running => status.state.as_str().success(),
+                JobState::BackingOff => status.state.as_str().time_value(),
+                JobState::Starting | JobState::Waiting | JobState::Stopped => {
+                    status.state.as_str().thick()
+                }
+            };
+
+            table.add_row(row![
+                bFC->&status.name,
+                state,
+                if status.state == JobState::Running && status.ready {
+                    "*"
+                } else {
+                    " "
+                },
+                status.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+                status
+                    .uptime()
+                    .map(|uptime| Folktime::duration(uptime).to_string())
+                    .unwrap_or_default(),
+                status.restart_count,
+                status.last_exit.as_deref().unwrap_or_default(),
+                status.last_hook.as_deref().unwrap_or_default(),
+                status.group,
+            ]);
+        }
+
+        table.printstd();
+    }
+}
@@ -9,26 +9,37 @@ pub enum Template {
 }
 
 impl Job {
+    /// Adds the hooks of a template. Hooks that the job already has are not added again.
     pub fn apply_template(&mut self, template: Template) {
         match template {
             Template::PortForward => {
-                self.event_hooks.push(Hook {
-                    name: "aborted hook".to_string(),
-                    event: Event::DetectSubstring {
+                self.add_template_hook(Hook::new(
+                    "aborted hook".to_string(),
+                    Event::DetectSubstring {
                         contains: "aborted".to_string(),
                         stream: Stream::Any,
                     },
-                    action: Action::Restart,
-                });
-                self.event_hooks.push(Hook {
-                    name: "connection lost hook".to_string(),
-                    event: Event::DetectSubstring {
+                    Action::Restart,
+                ));
+                self.add_template_hook(Hook::new(
+                    "connection lost hook".to_string(),
+                    Event::DetectSubstring {
                         contains: "connection lost".to_string(),
                         stream: Stream::Any,
                     },
-                    action: Action::Restart,
-                });
+                    Action::Restart,
+                ));
             }
         }
     }
+
+    fn add_template_hook(&mut self, hook: Hook) {
+        if !self
+            .event_hooks
+            .iter()
+            .any(|existing| existing.name == hook.name)
+        {
+            self.event_hooks.push(hook);
+        }
+    }
 }
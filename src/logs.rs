@@ -0,0 +1,354 @@
+use crate::{
+    colors::Tend,
+    job::{
+        Job,
+        event::Stream,
+        filter::Filter,
+        log::{JobLog, LogEntry, LogStream},
+    },
+};
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+#[derive(Debug)]
+pub struct LogsOptions {
+    pub follow: bool,
+    pub tail: Option<usize>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub stream: Stream,
+    pub timestamps: bool,
+}
+
+impl LogsOptions {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        let stream = match self.stream {
+            Stream::Any => true,
+            Stream::Stdout => entry.stream == LogStream::Stdout,
+            Stream::Stderr => entry.stream == LogStream::Stderr,
+        };
+
+        stream
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+    }
+
+    fn print(&self, job_name: &str, entry: &LogEntry) {
+        let timestamp = if self.timestamps {
+            format!(
+                "{} ",
+                entry
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S%.3f")
+            )
+        } else {
+            String::new()
+        };
+
+        match entry.stream {
+            LogStream::Stdout => {
+                println!("{timestamp}{}{}", format!("{job_name}: ").job(), entry.line);
+            }
+            LogStream::Stderr => println!(
+                "{timestamp}{}{}{}{}",
+                job_name.job(),
+                " (stderr)".failure(),
+                ": ".job(),
+                entry.line
+            ),
+            LogStream::Tend => println!(
+                "{timestamp}{}{}{}{}",
+                job_name.job(),
+                " (tend)".thick(),
+                ": ".job(),
+                entry.line
+            ),
+        }
+    }
+}
+
+/// Parses absolute times (RFC 3339 or `YYYY-MM-DD HH:MM:SS` in local time) and relative
+/// times such as `30s`, `10m`, `2h` or `1d` ago.
+pub fn parse_time(time: &str) -> Result<DateTime<Utc>, String> {
+    let time = time.trim();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(time) {
+        return Ok(time.with_timezone(&Utc));
+    }
+
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(time) = chrono::NaiveDateTime::parse_from_str(time, format) {
+            return time
+                .and_local_timezone(Local)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc))
+                .ok_or_else(|| format!("invalid local time: {time}"));
+        }
+    }
+
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        return date
+            .and_time(chrono::NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|time| time.with_timezone(&Utc))
+            .ok_or_else(|| format!("invalid local date: {time}"));
+    }
+
+    let Some((unit_start, _)) = time.char_indices().last() else {
+        return Err("empty time".to_string());
+    };
+    let (number, unit) = time.split_at(unit_start);
+    let ago = match unit {
+        "s" => chrono::Duration::try_seconds,
+        "m" => chrono::Duration::try_minutes,
+        "h" => chrono::Duration::try_hours,
+        "d" => chrono::Duration::try_days,
+        _ => {
+            return Err(format!(
+                "invalid time unit in {time}, expected s, m, h or d"
+            ));
+        }
+    };
+    let number: i64 = number
+        .parse()
+        .map_err(|e| format!("invalid time {time}: {e}"))?;
+
+    ago(number)
+        .and_then(|ago| Utc::now().checked_sub_signed(ago))
+        .ok_or_else(|| format!("time {time} is out of range"))
+}
+
+/// Current log file of a job being followed. The file is kept open, so lines written to it
+/// right before it is rotated are still read after it was renamed.
+#[derive(Debug)]
+struct Followed {
+    job_name: String,
+    path: PathBuf,
+    file: Option<File>,
+    partial: String,
+}
+
+impl Followed {
+    const fn new(job_name: String, path: PathBuf) -> Self {
+        Self {
+            job_name,
+            path,
+            file: None,
+            partial: String::new(),
+        }
+    }
+
+    /// Reads lines appended to the current log since the last call, the whole log on the
+    /// first call.
+    fn read_new(&mut self) -> Result<Vec<LogEntry>> {
+        let current = File::open(&self.path).ok();
+        let mut appended = String::new();
+
+        let rotated = match (&mut self.file, &current) {
+            (Some(file), Some(current)) => {
+                let rotated = replaced(file, current)?;
+                if !rotated && file.metadata()?.len() < file.stream_position()? {
+                    // The log was truncated, read it again from the beginning.
+                    file.seek(SeekFrom::Start(0))?;
+                    self.partial.clear();
+                }
+                // Drain the old file first. It is no longer written to once it was replaced.
+                BufReader::new(&mut *file).read_to_string(&mut appended)?;
+                rotated
+            }
+            (Some(file), None) => {
+                BufReader::new(&mut *file).read_to_string(&mut appended)?;
+                false
+            }
+            (None, _) => true,
+        };
+        if rotated && let Some(mut current) = current {
+            BufReader::new(&mut current).read_to_string(&mut appended)?;
+            self.file = Some(current);
+        }
+
+        self.partial.push_str(&appended);
+        let complete = self.partial.rfind('\n').map_or(0, |i| i + 1);
+        let entries = LogEntry::parse_all(&self.partial[..complete]);
+        self.partial.drain(..complete);
+
+        Ok(entries)
+    }
+}
+
+/// Whether the file at the log path, `current`, replaced the open `file` since it was opened.
+#[cfg(unix)]
+fn replaced(file: &File, current: &File) -> Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let (file, current) = (file.metadata()?, current.metadata()?);
+    Ok(file.dev() != current.dev() || file.ino() != current.ino())
+}
+
+/// Whether the file at the log path, `current`, replaced the open `file` since it was opened.
+/// Without stable file ids, a replacement is noticed only while it is shorter than what was
+/// read.
+#[cfg(not(unix))]
+fn replaced(file: &mut File, current: &File) -> Result<bool> {
+    Ok(current.metadata()?.len() < file.stream_position()?)
+}
+
+pub async fn logs(filter: &Filter, options: &LogsOptions, verbose: bool) -> Result<()> {
+    let mut job_names = vec![];
+    Job::iterate_jobs_filtered(|job| job_names.push(job.name), filter, true, verbose)?;
+
+    let mut history = vec![];
+    let mut followed = vec![];
+
+    for job_name in job_names {
+        if !JobLog::dir(&job_name)?.exists() {
+            if verbose {
+                println!("{} has no logs", job_name.job());
+            }
+            continue;
+        }
+
+        let mut entries = vec![];
+        for path in JobLog::rotated_files(&job_name)? {
+            entries.extend(LogEntry::parse_all(&JobLog::read_file(&path)?));
+        }
+
+        let mut current = Followed::new(job_name.clone(), JobLog::current_path(&job_name)?);
+        entries.extend(current.read_new()?);
+
+        entries.retain(|entry| options.matches(entry));
+        if let Some(tail) = options.tail {
+            entries.drain(..entries.len().saturating_sub(tail));
+        }
+        history.extend(entries.into_iter().map(|entry| (job_name.clone(), entry)));
+
+        followed.push(current);
+    }
+
+    if followed.is_empty() {
+        anyhow::bail!("No logs found. Enable logging with `tend edit <job> log enable`.");
+    }
+
+    history.sort_by_key(|(_, entry)| entry.timestamp);
+    for (job_name, entry) in &history {
+        options.print(job_name, entry);
+    }
+
+    if !options.follow {
+        return Ok(());
+    }
+
+    let mut interval = tokio::time::interval(std::time::Duration::from_millis(250));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                let mut entries = vec![];
+                for job in &mut followed {
+                    for entry in job.read_new()? {
+                        if options.matches(&entry) {
+                            entries.push((job.job_name.clone(), entry));
+                        }
+                    }
+                }
+
+                entries.sort_by_key(|(_, entry)| entry.timestamp);
+                for (job_name, entry) in &entries {
+                    options.print(job_name, entry);
+                }
+            }
+
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Followed, parse_time};
+    use chrono::Utc;
+    use std::io::Write;
+
+    fn lines(followed: &mut Followed) -> Vec<String> {
+        followed
+            .read_new()
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.line)
+            .collect()
+    }
+
+    fn append(path: &std::path::Path, lines: &[&str]) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        for line in lines {
+            writeln!(file, "2026-01-01T00:00:00Z stdout {line}").unwrap();
+        }
+    }
+
+    #[test]
+    fn follows_rotated_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let current = dir.path().join("output.log");
+        let mut followed = Followed::new("job".to_string(), current.clone());
+        assert!(lines(&mut followed).is_empty());
+
+        append(&current, &["one"]);
+        assert_eq!(lines(&mut followed), ["one"]);
+
+        // Lines written right before the rotation are read from the rotated file, even once
+        // the new file grew past the position read up to.
+        append(&current, &["two"]);
+        std::fs::rename(&current, dir.path().join("output.1.log")).unwrap();
+        append(&current, &["three", "four", "five"]);
+        assert_eq!(lines(&mut followed), ["two", "three", "four", "five"]);
+
+        append(&current, &["six"]);
+        assert_eq!(lines(&mut followed), ["six"]);
+    }
+
+    #[test]
+    fn follows_truncated_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let current = dir.path().join("output.log");
+        append(&current, &["one", "two"]);
+        let mut followed = Followed::new("job".to_string(), current.clone());
+        assert_eq!(lines(&mut followed), ["one", "two"]);
+
+        std::fs::File::create(&current).unwrap();
+        append(&current, &["three"]);
+        assert_eq!(lines(&mut followed), ["three"]);
+    }
+
+    #[test]
+    fn parses_relative_times() {
+        let since = parse_time("5m").unwrap();
+        assert_eq!((Utc::now() - since).num_minutes(), 5);
+        let since = parse_time("2d").unwrap();
+        assert_eq!((Utc::now() - since).num_hours(), 48);
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        assert!(parse_time("5w").is_err());
+        assert!(parse_time("5é").is_err());
+        assert!(parse_time("é").is_err());
+        assert!(parse_time("").is_err());
+    }
+
+    #[test]
+    fn rejects_times_out_of_range() {
+        assert!(parse_time("9223372036854775807d").is_err());
+    }
+}
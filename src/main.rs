@@ -86,9 +86,19 @@
 
 use crate::colors::Tend;
 mod args;
+mod bundle;
 mod colors;
+mod config;
+#[cfg(unix)]
+mod daemon;
+mod editor;
+mod import;
 mod job;
+mod logs;
+mod paths;
 mod run;
+mod supervisor;
+mod systemd;
 
 use crate::job::{Job, filter::Filter};
 use anyhow::Result;
@@ -120,6 +130,70 @@ fn standard_job_filter(
     }
 }
 
+fn hook_action(action: args::HookAction) -> Result<job::event::Action> {
+    use args::HookActionKind as Kind;
+
+    let mut command = action.command.into_iter();
+    if !matches!(action.action, Kind::Exec) && command.len() > 0 {
+        anyhow::bail!("Only the exec action takes a command");
+    }
+    if !matches!(action.action, Kind::Signal) && action.send.is_some() {
+        anyhow::bail!("Only the signal action takes a --send signal");
+    }
+    let targets_others = !matches!(
+        action.action,
+        Kind::Restart | Kind::Stop | Kind::Exec | Kind::Signal
+    );
+    let target = match action.target {
+        Some(_) if !targets_others => {
+            anyhow::bail!("Only actions on other jobs or groups take a --target")
+        }
+        None if targets_others => anyhow::bail!("This action needs a --target job or group"),
+        target => target.unwrap_or_default(),
+    };
+    Ok(match action.action {
+        Kind::Restart => job::event::Action::Restart,
+        Kind::Stop => job::event::Action::Stop,
+        Kind::Exec => {
+            let Some(program) = command.next() else {
+                anyhow::bail!(
+                    "The exec action needs a command, e.g. exec -- notify-send \"$TEND_LINE\""
+                );
+            };
+            job::event::Action::Exec {
+                program,
+                args: command.collect(),
+                timeout_seconds: action.timeout,
+            }
+        }
+        Kind::RestartJob => job::event::Action::RestartJob(target),
+        Kind::StopJob => job::event::Action::StopJob(target),
+        Kind::StartJob => job::event::Action::StartJob(target),
+        Kind::RestartGroup => job::event::Action::RestartGroup(target),
+        Kind::StopGroup => job::event::Action::StopGroup(target),
+        Kind::StartGroup => job::event::Action::StartGroup(target),
+        Kind::Signal => job::event::Action::Signal(
+            action
+                .send
+                .ok_or_else(|| anyhow::anyhow!("The signal action needs a --send signal"))?,
+        ),
+    })
+}
+
+#[cfg(unix)]
+fn print_daemon_jobs(response: daemon::protocol::Response, state: &str, empty: &str) {
+    match response {
+        daemon::protocol::Response::Jobs(names) if names.is_empty() => println!("{empty}"),
+        daemon::protocol::Response::Jobs(names) => {
+            for name in names {
+                println!("{} {}", name.job(), state);
+            }
+        }
+        daemon::protocol::Response::Status(_) => (),
+        daemon::protocol::Response::Error(e) => eprintln!("{}", e.failure()),
+    }
+}
+
 #[tokio::main]
 #[allow(clippy::too_many_lines)]
 async fn main() -> Result<()> {
@@ -150,11 +224,157 @@ async fn main() -> Result<()> {
             job,
             all,
             exclude,
+            foreground,
         } => {
             let filter = standard_job_filter(name, all, group, job, exclude);
 
+            #[cfg(unix)]
+            if !foreground && let Some(mut client) = daemon::client::Client::connect().await? {
+                return client.start(&filter, args.verbose).await;
+            }
+            #[cfg(not(unix))]
+            let _ = foreground;
+
             run::run(filter, args.verbose).await?;
         }
+        #[cfg(unix)]
+        args::Commands::Stop {
+            name,
+            group,
+            job,
+            all,
+            exclude,
+        } => {
+            let filter = standard_job_filter(name, all, group, job, exclude);
+            let response =
+                daemon::client::request(&daemon::protocol::Request::Stop { filter }).await?;
+            print_daemon_jobs(response, "stopping", "No running jobs matched.");
+        }
+        #[cfg(unix)]
+        args::Commands::Restart {
+            name,
+            group,
+            job,
+            all,
+            exclude,
+        } => {
+            let filter = standard_job_filter(name, all, group, job, exclude);
+            let response =
+                daemon::client::request(&daemon::protocol::Request::Restart { filter }).await?;
+            print_daemon_jobs(response, "restarting", "No running jobs matched.");
+        }
+        #[cfg(unix)]
+        args::Commands::Status {
+            name,
+            group,
+            job,
+            all,
+            exclude,
+        } => {
+            let filter = standard_job_filter(name, all, group, job, exclude);
+            let daemon::protocol::Response::Status(statuses) =
+                daemon::client::request(&daemon::protocol::Request::Status { filter }).await?
+            else {
+                anyhow::bail!("Unexpected response from daemon.");
+            };
+            job::status::JobStatus::print_table(&statuses);
+        }
+        #[cfg(unix)]
+        args::Commands::Daemon { command } => match command {
+            args::DaemonCommands::Start { foreground } => {
+                if foreground {
+                    daemon::serve(args.verbose).await?;
+                } else {
+                    daemon::spawn_detached(args.verbose).await?;
+                }
+            }
+            args::DaemonCommands::Stop => {
+                let response =
+                    daemon::client::request(&daemon::protocol::Request::Shutdown).await?;
+                print_daemon_jobs(response, "stopping", "Daemon stopped.");
+            }
+        },
+        args::Commands::Export {
+            name,
+            group,
+            job,
+            all,
+            exclude,
+            output,
+            format,
+            relative_to,
+            systemd,
+            targets,
+        } => {
+            let filter = standard_job_filter(name, all, group, job, exclude);
+            if systemd {
+                let options = systemd::SystemdOptions { output, targets };
+                return systemd::export(&filter, &options, args.verbose);
+            }
+
+            let options = bundle::ExportOptions {
+                output,
+                format,
+                relative_to: relative_to.map(std::fs::canonicalize).transpose()?,
+            };
+            bundle::export(&filter, &options, args.verbose)?;
+        }
+        args::Commands::Import {
+            file,
+            format,
+            procfile,
+            compose,
+            supervisord,
+            pm2,
+            overwrite,
+            rename_prefix,
+            group,
+            base,
+        } => {
+            let options = bundle::ImportOptions {
+                overwrite,
+                rename_prefix,
+                group,
+                base: std::env::current_dir()?.join(base.unwrap_or_default()),
+            };
+            let jobs = if procfile {
+                import::procfile::read(&file)?
+            } else if compose {
+                import::compose::read(&file)?
+            } else if supervisord {
+                import::supervisord::read(&file)?
+            } else if pm2 {
+                import::pm2::read(&file)?
+            } else {
+                bundle::read(&file, format)?.jobs
+            };
+            bundle::import(jobs, &options);
+        }
+        args::Commands::Logs {
+            name,
+            all,
+            group,
+            job,
+            exclude,
+            follow,
+            tail,
+            since,
+            until,
+            stream,
+            timestamps,
+        } => {
+            let filter = standard_job_filter(name, all, group, job, exclude);
+            let options = logs::LogsOptions {
+                follow,
+                tail,
+                since,
+                until,
+                stream,
+                timestamps,
+            };
+
+            logs::logs(&filter, &options, args.verbose).await?;
+        }
         args::Commands::Create {
             name,
             program,
@@ -163,26 +383,41 @@ async fn main() -> Result<()> {
             group,
             overwrite,
             restart_strategy,
+            max_restarts,
+            stop_signal,
+            stop_timeout,
             template,
+            depends_on,
+            log,
+            log_max_size,
+            log_max_files,
+            env,
+            env_file,
+            format,
         } => {
-            let mut job = Job {
-                name,
-                enabled: true,
-                program,
-                args,
+            let log = log.then(|| {
+                let mut config = job::log::LogConfig::default();
+                config.max_size = log_max_size.unwrap_or(config.max_size);
+                config.max_files = log_max_files.unwrap_or(config.max_files);
+                config
+            });
+
+            let job = Job {
                 restart,
                 group,
-                working_directory: std::env::current_dir()?,
                 restart_strategy,
-                event_hooks: vec![],
+                max_restarts,
+                stop_signal,
+                stop_timeout_seconds: stop_timeout,
                 template,
+                log,
+                depends_on,
+                env: env.into_iter().collect(),
+                env_file,
+                ..Job::new(name, program, args, std::env::current_dir()?)
             };
 
-            if let Some(template) = template {
-                job.apply_template(template);
-            }
-
-            let res = job.save(overwrite);
+            let res = job.save_as(format, overwrite);
             if let Err(ref error) = res {
                 if let Some(error) = error.downcast_ref::<std::io::Error>() {
                     if error.kind() == std::io::ErrorKind::AlreadyExists {
@@ -208,7 +443,9 @@ async fn main() -> Result<()> {
             Job::iterate_jobs_filtered(
                 |mut job| {
                     job.enabled = true;
-                    let _ = job.save(true);
+                    if let Err(e) = job.save(true) {
+                        eprintln!("{}", e.to_string().failure());
+                    }
                 },
                 &filter,
                 true,
@@ -227,44 +464,258 @@ async fn main() -> Result<()> {
             Job::iterate_jobs_filtered(
                 |mut job| {
                     job.enabled = false;
-                    let _ = job.save(true);
+                    if let Err(e) = job.save(true) {
+                        eprintln!("{}", e.to_string().failure());
+                    }
                 },
                 &filter,
                 true,
                 args.verbose,
             )?;
         }
-        args::Commands::Edit { name, command } => {
-            let mut job = Job::load(&name, args.verbose)
+        args::Commands::Edit {
+            name,
+            editor,
+            command,
+        } => {
+            let mut job = Job::load_unresolved(&name, args.verbose)
                 .ok_or_else(|| anyhow::anyhow!("Job could not be loaded."))?;
+            job.ensure_editable()?;
+            let command = match (command, editor) {
+                (Some(_), true) => anyhow::bail!("--editor cannot be combined with a subcommand."),
+                (Some(command), false) => command,
+                (None, true) => return editor::edit(&job),
+                (None, false) => anyhow::bail!("Specify what to edit, or use --editor."),
+            };
+            let mut format = None;
             match command {
+                args::EditJobCommands::Format { format: new_format } => format = Some(new_format),
+                args::EditJobCommands::Rename { new_name } => {
+                    job.rename(&new_name, args.verbose)?;
+                    println!("Job {} renamed to {}", name.job(), new_name.job());
+                }
+                args::EditJobCommands::Program { program } => job.program = program,
+                args::EditJobCommands::Args { args } => job.args = args,
+                args::EditJobCommands::WorkingDirectory { path } => {
+                    let current_dir = std::env::current_dir()?;
+                    job.working_directory = match path {
+                        Some(path) => {
+                            std::fs::canonicalize(current_dir.join(&path)).map_err(|e| {
+                                anyhow::anyhow!("Invalid working directory {}: {e}", path.display())
+                            })?
+                        }
+                        None => current_dir,
+                    };
+                }
+                args::EditJobCommands::Restart { restart } => job.restart = restart,
+                args::EditJobCommands::RestartStrategy { restart_strategy } => {
+                    job.restart_strategy = restart_strategy;
+                }
+                args::EditJobCommands::MaxRestarts { count } => job.max_restarts = count,
+                args::EditJobCommands::Stop { signal, timeout } => {
+                    job.stop_signal = signal;
+                    if let Some(timeout) = timeout {
+                        job.stop_timeout_seconds = timeout;
+                    }
+                }
+                args::EditJobCommands::Template { template } => job.template = template,
                 args::EditJobCommands::Group { group } => job.group = group,
+                args::EditJobCommands::DependsOn { jobs } => job.depends_on = jobs,
+                args::EditJobCommands::Readiness {
+                    interval,
+                    timeout,
+                    command,
+                } => {
+                    let mut readiness = job.readiness.take().unwrap_or_default();
+                    readiness.interval_seconds = interval.unwrap_or(readiness.interval_seconds);
+                    readiness.timeout_seconds = timeout.unwrap_or(readiness.timeout_seconds);
+
+                    match command {
+                        None => (),
+                        Some(args::EditJobReadinessCommands::List) => {
+                            if readiness.probes.is_empty() {
+                                println!("No readiness probes defined for job {}", job.name);
+                            } else {
+                                for probe in &readiness.probes {
+                                    println!("{probe}");
+                                }
+                            }
+                        }
+                        Some(args::EditJobReadinessCommands::LogLine { substring, stream }) => {
+                            readiness.probes.push(job::probe::ReadinessProbe::LogLine {
+                                stream,
+                                contains: substring,
+                            });
+                        }
+                        Some(args::EditJobReadinessCommands::Tcp { port }) => {
+                            readiness
+                                .probes
+                                .push(job::probe::ReadinessProbe::Tcp { port });
+                        }
+                        Some(args::EditJobReadinessCommands::Exec { program, args }) => {
+                            readiness
+                                .probes
+                                .push(job::probe::ReadinessProbe::Exec { program, args });
+                        }
+                        Some(args::EditJobReadinessCommands::Clear) => readiness.probes.clear(),
+                    }
+
+                    if !readiness.probes.is_empty() {
+                        job.readiness = Some(readiness);
+                    }
+                }
+                args::EditJobCommands::Liveness {
+                    interval,
+                    timeout,
+                    failure_threshold,
+                    command,
+                } => {
+                    let probe = match command {
+                        args::EditJobLivenessCommands::List => {
+                            if job.liveness.is_empty() {
+                                println!("No liveness checks defined for job {}", job.name);
+                            } else {
+                                for check in &job.liveness {
+                                    println!("{check}");
+                                }
+                            }
+                            None
+                        }
+                        args::EditJobLivenessCommands::Tcp { port } => {
+                            Some(job::probe::LivenessProbe::Tcp { port })
+                        }
+                        args::EditJobLivenessCommands::Http { url } => {
+                            Some(job::probe::LivenessProbe::Http { url })
+                        }
+                        args::EditJobLivenessCommands::Exec { program, args } => {
+                            Some(job::probe::LivenessProbe::Exec { program, args })
+                        }
+                        args::EditJobLivenessCommands::Clear => {
+                            job.liveness.clear();
+                            None
+                        }
+                    };
+
+                    if let Some(probe) = probe {
+                        job.liveness.push(job::probe::LivenessCheck {
+                            probe,
+                            interval_seconds: interval,
+                            timeout_seconds: timeout,
+                            failure_threshold,
+                        });
+                    }
+                }
+                args::EditJobCommands::Env { command } => match command {
+                    args::EditJobEnvCommands::List => {
+                        for (key, value) in &job.env {
+                            println!("{key}={value}");
+                        }
+                        if let Some(env_file) = &job.env_file {
+                            println!("env file: {}", env_file.display());
+                        }
+                        if job.clear_env {
+                            println!("inherited variables: {}", job.env_allow.join(", "));
+                        }
+                    }
+                    args::EditJobEnvCommands::Set { vars } => job.env.extend(vars),
+                    args::EditJobEnvCommands::Unset { keys } => {
+                        for key in keys {
+                            if job.env.remove(&key).is_none() {
+                                eprintln!("Variable {key} not found");
+                            }
+                        }
+                    }
+                    args::EditJobEnvCommands::File { path } => job.env_file = path,
+                    args::EditJobEnvCommands::Clear { allow } => {
+                        job.clear_env = true;
+                        job.env_allow = allow;
+                    }
+                    args::EditJobEnvCommands::Inherit => {
+                        job.clear_env = false;
+                        job.env_allow.clear();
+                    }
+                },
+                args::EditJobCommands::Log { command } => match command {
+                    args::EditJobLogCommands::Enable {
+                        max_size,
+                        max_files,
+                        no_compress,
+                    } => {
+                        let mut config = job.log.take().unwrap_or_default();
+                        config.max_size = max_size.unwrap_or(config.max_size);
+                        config.max_files = max_files.unwrap_or(config.max_files);
+                        config.compress = !no_compress;
+                        job.log = Some(config);
+                    }
+                    args::EditJobLogCommands::Disable => job.log = None,
+                },
                 args::EditJobCommands::Hook { command } => match command {
                     args::EditJobHookCommands::List => {
-                        if job.event_hooks.is_empty() {
+                        let mut resolved = job.clone();
+                        if let Some(template) = job.template {
+                            resolved.apply_template(template);
+                        }
+                        if resolved.event_hooks.is_empty() {
                             println!("No hooks defined for job {}", job.name);
                         } else {
-                            for hook in &job.event_hooks {
+                            for hook in &resolved.event_hooks {
                                 println!("{hook:?}");
                             }
                         }
+                        println!("Default hooks from restart behavior:");
+                        for hook in job.restart.default_hooks() {
+                            println!("{hook:?}");
+                        }
                     }
-                    args::EditJobHookCommands::Create { hook, t } => match t {
-                        args::JobHook::DetectSubstring {
-                            substring,
-                            stream,
-                            action,
-                        } => {
-                            job.event_hooks.push(job::event::Hook {
-                                name: hook,
-                                event: job::event::Event::DetectSubstring {
+                    args::EditJobHookCommands::Create {
+                        hook,
+                        count,
+                        within,
+                        cooldown,
+                        t,
+                    } => {
+                        let (event, action) = match t {
+                            args::JobHook::FinishedSuccess { action } => {
+                                (job::event::Event::FinishedSuccess, action)
+                            }
+                            args::JobHook::FinishedFailure { action } => {
+                                (job::event::Event::FinishedFailure, action)
+                            }
+                            args::JobHook::Exit {
+                                action,
+                                codes,
+                                signals,
+                            } => (job::event::Event::Exit { codes, signals }, action),
+                            args::JobHook::DetectSubstring {
+                                substring,
+                                stream,
+                                action,
+                            } => (
+                                job::event::Event::DetectSubstring {
                                     contains: substring,
                                     stream,
                                 },
                                 action,
-                            });
-                        }
-                    },
+                            ),
+                            args::JobHook::DetectRegex {
+                                pattern,
+                                stream,
+                                action,
+                            } => (job::event::Event::DetectRegex { stream, pattern }, action),
+                            args::JobHook::Silence {
+                                seconds,
+                                stream,
+                                action,
+                            } => (job::event::Event::Silence { stream, seconds }, action),
+                        };
+                        let action = hook_action(action)?;
+                        job.event_hooks.push(job::event::Hook {
+                            count,
+                            within_seconds: within,
+                            cooldown_seconds: cooldown,
+                            ..job::event::Hook::new(hook, event, action)
+                        });
+                    }
                     args::EditJobHookCommands::Delete { hook: hook_name } => {
                         let mut deleted = false;
                         job.event_hooks.retain(|hook| {
@@ -282,7 +733,7 @@ async fn main() -> Result<()> {
                     }
                 },
             }
-            job.save(true)?;
+            job.save_as(format, true)?;
         }
         args::Commands::Delete {
             name,
@@ -317,7 +768,9 @@ async fn main() -> Result<()> {
                 } else {
                     Job::iterate_jobs_filtered(
                         |job| {
-                            let _ = job.delete();
+                            if let Err(e) = job.delete() {
+                                eprintln!("{}", e.to_string().failure());
+                            }
                         },
                         &filter,
                         true,
@@ -0,0 +1,28 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+pub fn tend_dir() -> Result<PathBuf> {
+    let home =
+        dirs_next::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
+    let tend = home.join(".tend");
+    std::fs::create_dir_all(&tend)?;
+    Ok(tend)
+}
+
+pub fn config_path() -> Result<PathBuf> {
+    Ok(tend_dir()?.join("config.toml"))
+}
+
+pub fn logs_dir() -> Result<PathBuf> {
+    Ok(tend_dir()?.join("logs"))
+}
+
+#[cfg(unix)]
+pub fn socket_path() -> Result<PathBuf> {
+    Ok(tend_dir()?.join("tend.sock"))
+}
+
+#[cfg(unix)]
+pub fn daemon_log_path() -> Result<PathBuf> {
+    Ok(tend_dir()?.join("daemon.log"))
+}
@@ -1,46 +1,37 @@
-use crate::{Job, job::filter::Filter};
-use tokio::sync::mpsc;
+use crate::{
+    Job,
+    job::filter::Filter,
+    supervisor::{ShutdownSignals, Supervisor},
+};
 
 pub async fn run(job_filter: Filter, verbose: bool) -> anyhow::Result<()> {
-    let mut join_set = tokio::task::JoinSet::new();
-    let mut cancel_handles = vec![];
+    let mut jobs = vec![];
+    Job::iterate_jobs_filtered(|job| jobs.push(job), &job_filter, false, verbose)?;
 
-    let mut count = 0;
-
-    Job::iterate_jobs_filtered(
-        |job| {
-            count += 1;
-
-            let (tx, rx) = mpsc::channel::<()>(1);
-            cancel_handles.push(tx);
-            join_set.spawn(job.create_repeated_process(rx, verbose));
-        },
-        &job_filter,
-        false,
-        verbose,
-    )?;
-
-    if count == 0 {
+    if jobs.is_empty() {
         anyhow::bail!("No jobs matched.");
     }
 
+    let mut signals = ShutdownSignals::new()?;
+    let mut supervisor = Supervisor::new(verbose);
+    for job in Job::resolve_dependencies(jobs, verbose)? {
+        supervisor.start(job);
+    }
+
     loop {
         tokio::select! {
-            a = join_set.join_next() => {
-                if a.is_none() {
+            finished = supervisor.join_next() => {
+                let Some((name, result)) = finished else {
                     if verbose {
                         println!("All jobs finished.");
                     }
                     break;
-                }
+                };
+                supervisor.report(&name, &result);
             }
 
-            _ = tokio::signal::ctrl_c() => {
-                for tx in &cancel_handles {
-                    let _ = tx.send(()).await;
-                }
-
-                join_set.shutdown().await;
+            () = signals.recv() => {
+                supervisor.shutdown().await;
             }
         }
     }
@@ -0,0 +1,388 @@
+use crate::{
+    colors::Tend,
+    job::{
+        Job, JobCommand, JobContext,
+        dependency::Dependency,
+        filter::Filter,
+        log::JobLog,
+        status::{JobState, JobStatus},
+    },
+};
+use std::collections::{BTreeMap, BTreeSet};
+use tokio::{
+    sync::{mpsc, watch},
+    task::{Id, JoinSet},
+};
+
+/// Signals that make tend stop its jobs: Ctrl-C, and on Unix also SIGTERM and SIGHUP, which a
+/// terminal sends when it is closed. Jobs run in their own process groups, so they do not get
+/// these signals themselves.
+#[derive(Debug)]
+pub struct ShutdownSignals {
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    hangup: tokio::signal::unix::Signal,
+}
+
+impl ShutdownSignals {
+    #[cfg(unix)]
+    pub fn new() -> std::io::Result<Self> {
+        use tokio::signal::unix::{SignalKind, signal};
+        Ok(Self {
+            terminate: signal(SignalKind::terminate())?,
+            hangup: signal(SignalKind::hangup())?,
+        })
+    }
+
+    #[cfg(windows)]
+    #[allow(clippy::unnecessary_wraps)]
+    pub const fn new() -> std::io::Result<Self> {
+        Ok(Self {})
+    }
+
+    /// Waits for the next signal.
+    #[cfg(unix)]
+    pub async fn recv(&mut self) {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => (),
+            _ = self.terminate.recv() => (),
+            _ = self.hangup.recv() => (),
+        }
+    }
+
+    /// Waits for the next signal.
+    #[cfg(windows)]
+    pub async fn recv(&mut self) {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Commands sent by hooks to act on jobs of their supervisor.
+#[derive(Debug)]
+pub enum SupervisorCommand {
+    /// Starts matching jobs that are not running, loading them with their dependencies.
+    Start(Filter),
+    Stop(Filter),
+    Restart(Filter),
+}
+
+#[derive(Debug)]
+struct RunningJob {
+    job: Job,
+    tx: mpsc::Sender<JobCommand>,
+    status: watch::Receiver<JobStatus>,
+    task: Id,
+}
+
+/// Owns the tasks of all jobs started in a single `tend run` session or daemon.
+#[derive(Debug)]
+pub struct Supervisor {
+    join_set: JoinSet<anyhow::Result<()>>,
+    jobs: BTreeMap<String, RunningJob>,
+    stopped: BTreeMap<String, (Job, JobStatus)>,
+    commands_tx: mpsc::UnboundedSender<SupervisorCommand>,
+    commands: mpsc::UnboundedReceiver<SupervisorCommand>,
+    verbose: bool,
+}
+
+impl Supervisor {
+    pub fn new(verbose: bool) -> Self {
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+        Self {
+            join_set: JoinSet::new(),
+            jobs: BTreeMap::new(),
+            stopped: BTreeMap::new(),
+            commands_tx,
+            commands,
+            verbose,
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Starts the job unless a job with the same name is already running.
+    pub fn start(&mut self, job: Job) -> bool {
+        if self.jobs.contains_key(&job.name) {
+            return false;
+        }
+
+        let (tx, rx) = mpsc::channel::<JobCommand>(1);
+        let (status_tx, status) =
+            watch::channel(JobStatus::new(job.name.clone(), job.group.clone()));
+        let log = JobLog::open(&job).unwrap_or_else(|e| {
+            eprintln!(
+                "{} {}: {}",
+                job.name.job(),
+                "could not open log".failure(),
+                e
+            );
+            None
+        });
+        let dependencies = job
+            .depends_on
+            .iter()
+            .filter_map(|name| {
+                if let Some(running) = self.jobs.get(name) {
+                    return Some(Dependency {
+                        name: name.clone(),
+                        status: running.status.clone(),
+                    });
+                }
+                if !self.stopped.contains_key(name) {
+                    eprintln!(
+                        "{} {} {}",
+                        job.name.job(),
+                        "depends on a job that is not running:".failure(),
+                        name.job()
+                    );
+                }
+                None
+            })
+            .collect();
+        let context = JobContext {
+            rx,
+            status: status_tx,
+            log,
+            dependencies,
+            verbose: self.verbose,
+            supervisor: self.commands_tx.clone(),
+            hook_commands: tokio::task::JoinSet::new(),
+        };
+        let handle = self
+            .join_set
+            .spawn(job.clone().create_repeated_process(context));
+        self.stopped.remove(&job.name);
+        self.jobs.insert(
+            job.name.clone(),
+            RunningJob {
+                job,
+                tx,
+                status,
+                task: handle.id(),
+            },
+        );
+
+        true
+    }
+
+    #[cfg(unix)]
+    pub fn running(&self, filter: &Filter) -> Vec<String> {
+        self.jobs
+            .values()
+            .filter(|running| filter.matches(&running.job))
+            .map(|running| running.job.name.clone())
+            .collect()
+    }
+
+    /// Returns the status of every job matching `filter`, including jobs that have stopped.
+    #[cfg(unix)]
+    pub fn statuses(&self, filter: &Filter) -> Vec<JobStatus> {
+        let running = self
+            .jobs
+            .values()
+            .filter(|running| filter.matches(&running.job))
+            .map(|running| running.status.borrow().clone());
+        let stopped = self
+            .stopped
+            .values()
+            .filter(|(job, _)| filter.matches(job))
+            .map(|(_, status)| status.clone());
+
+        let mut statuses: Vec<JobStatus> = running.chain(stopped).collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name));
+        statuses
+    }
+
+    /// Sends `command` to every running job matching `filter` and returns their names.
+    pub fn send(&self, command: JobCommand, filter: &Filter) -> Vec<String> {
+        let mut names = vec![];
+        for running in self.jobs.values() {
+            if filter.matches(&running.job) {
+                // A full channel means the job already has a command pending.
+                let _ = running.tx.try_send(command);
+                names.push(running.job.name.clone());
+            }
+        }
+        names
+    }
+
+    /// Runs a command sent by a hook.
+    fn command(&mut self, command: SupervisorCommand) {
+        match command {
+            SupervisorCommand::Start(filter) => {
+                let mut jobs = vec![];
+                let loaded =
+                    Job::iterate_jobs_filtered(|job| jobs.push(job), &filter, false, self.verbose)
+                        .and_then(|()| Job::resolve_dependencies(jobs, self.verbose));
+                match loaded {
+                    Ok(jobs) => {
+                        for job in jobs {
+                            let name = job.name.clone();
+                            if self.start(job) && self.verbose {
+                                println!("{} started by a hook", name.job());
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("{} {}", "Could not start jobs:".failure(), e),
+                }
+            }
+            SupervisorCommand::Stop(filter) => {
+                self.send(JobCommand::Stop, &filter);
+            }
+            SupervisorCommand::Restart(filter) => {
+                self.send(JobCommand::Restart, &filter);
+            }
+        }
+    }
+
+    /// Waits for the next job to finish, running commands sent by hooks meanwhile. Returns
+    /// `None` when no jobs are running.
+    pub async fn join_next(&mut self) -> Option<(String, anyhow::Result<()>)> {
+        loop {
+            let joined = tokio::select! {
+                biased;
+                Some(command) = self.commands.recv() => {
+                    self.command(command);
+                    continue;
+                }
+                joined = self.join_set.join_next_with_id() => joined?,
+            };
+            let (id, result) = match joined {
+                Ok((id, result)) => (id, result),
+                Err(e) => (e.id(), Err(e.into())),
+            };
+
+            let Some(name) = self
+                .jobs
+                .iter()
+                .find(|(_, running)| running.task == id)
+                .map(|(name, _)| name.clone())
+            else {
+                continue;
+            };
+            if let Some(running) = self.jobs.remove(&name) {
+                let mut status = running.status.borrow().clone();
+                // A job that failed may not have stopped its processes.
+                #[cfg(unix)]
+                if let Some(pgid) = status.process_group.take() {
+                    crate::job::signal::kill_group(pgid);
+                }
+                status.state = JobState::Stopped;
+                status.pid = None;
+                self.stopped.insert(name.clone(), (running.job, status));
+            }
+
+            return Some((name, result));
+        }
+    }
+
+    /// Reports a finished job.
+    pub fn report(&self, name: &str, result: &anyhow::Result<()>) {
+        match result {
+            Ok(()) => {
+                if self.verbose {
+                    println!("{} finished", name.job());
+                }
+            }
+            Err(e) => eprintln!("{} {}: {}", name.job(), "failed".failure(), e),
+        }
+    }
+
+    /// Asks every job to stop and waits for them to terminate their processes. Jobs are stopped
+    /// in reverse dependency order: a job is stopped only after all jobs depending on it.
+    pub async fn shutdown(&mut self) {
+        self.commands.close();
+        while self.commands.try_recv().is_ok() {}
+        // Jobs wait for their dependents to stop, so their stop timeouts add up.
+        let mut chains = BTreeMap::new();
+        let stop_timeout = self
+            .jobs
+            .keys()
+            .map(|name| self.stop_chain_seconds(name, &mut chains))
+            .max()
+            .unwrap_or(0);
+        let grace_period = std::time::Duration::from_secs(10 + stop_timeout);
+        let _ = tokio::time::timeout(grace_period, async {
+            let mut stopping = BTreeSet::new();
+            loop {
+                for (name, running) in &self.jobs {
+                    let has_dependents = self
+                        .jobs
+                        .values()
+                        .any(|other| other.job.depends_on.contains(name));
+                    if !has_dependents && stopping.insert(name.clone()) {
+                        // Fails only if the job already finished.
+                        let _ = running.tx.send(JobCommand::Stop).await;
+                    }
+                }
+
+                let Some((name, result)) = self.join_next().await else {
+                    break;
+                };
+                self.report(&name, &result);
+            }
+        })
+        .await;
+
+        #[cfg(unix)]
+        self.kill_process_groups();
+        self.join_set.shutdown().await;
+        self.jobs.clear();
+    }
+
+    /// Longest time stopping the job can take: its stop timeout after those of all jobs
+    /// depending on it.
+    fn stop_chain_seconds(&self, name: &str, chains: &mut BTreeMap<String, u64>) -> u64 {
+        if let Some(seconds) = chains.get(name) {
+            return *seconds;
+        }
+        let Some(running) = self.jobs.get(name) else {
+            return 0;
+        };
+        // Guards against cycles, which dependency resolution already rejects.
+        chains.insert(name.to_string(), 0);
+        let dependents = self
+            .jobs
+            .values()
+            .filter(|other| {
+                other
+                    .job
+                    .depends_on
+                    .iter()
+                    .any(|dependency| dependency == name)
+            })
+            .map(|other| other.job.name.clone())
+            .collect::<Vec<_>>();
+        let seconds = running.job.stop_timeout_seconds
+            + dependents
+                .iter()
+                .map(|dependent| self.stop_chain_seconds(dependent, chains))
+                .max()
+                .unwrap_or(0);
+        chains.insert(name.to_string(), seconds);
+        seconds
+    }
+
+    /// Kills the processes of jobs that have not stopped them.
+    #[cfg(unix)]
+    fn kill_process_groups(&self) {
+        for running in self.jobs.values() {
+            let process_group = running.status.borrow().process_group;
+            if let Some(pgid) = process_group {
+                crate::job::signal::kill_group(pgid);
+            }
+        }
+    }
+}
+
+/// Kills the processes of jobs still running when tend exits.
+#[cfg(unix)]
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.kill_process_groups();
+    }
+}
@@ -0,0 +1,363 @@
+use crate::{
+    colors::Tend,
+    job::{
+        Job,
+        event::{RestartBehavior, RestartStrategy},
+        filter::Filter,
+    },
+};
+use anyhow::Result;
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub struct SystemdOptions {
+    /// Directory to write the units to, e.g. `~/.config/systemd/user`. Units are printed to
+    /// stdout if this is `None`.
+    pub output: Option<PathBuf>,
+    /// Also generate a `.target` per group that starts all jobs of the group.
+    pub targets: bool,
+}
+
+/// Turns a name into a valid unit name by replacing unsupported characters.
+fn unit_name(name: &str, suffix: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, ':' | '_' | '.' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("tend-{name}.{suffix}")
+}
+
+/// Quotes a word for use in `ExecStart=` or `Environment=`. Only `ExecStart=` expands
+/// variables, so `$` is escaped only there.
+fn quote(word: &str, escape_variables: bool) -> String {
+    let mut escaped = word
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    if escape_variables {
+        escaped = escaped.replace('$', "$$");
+    }
+    if word.is_empty() || escaped != word || word.contains(char::is_whitespace) {
+        format!("\"{escaped}\"")
+    } else {
+        escaped
+    }
+}
+
+/// Resolves a program the way the job would find it, since systemd searches a fixed `PATH`.
+fn resolve_program(job: &Job) -> PathBuf {
+    let program = Path::new(&job.program);
+    if program.components().count() > 1 {
+        return job.working_directory.join(program);
+    }
+
+    std::env::var_os("PATH")
+        .and_then(|path| {
+            std::env::split_paths(&path)
+                .map(|dir| dir.join(program))
+                .find(|candidate| candidate.is_file())
+        })
+        .unwrap_or_else(|| program.to_path_buf())
+}
+
+const fn restart(restart: RestartBehavior) -> &'static str {
+    match restart {
+        RestartBehavior::Always => "always",
+        RestartBehavior::OnSuccess => "on-success",
+        RestartBehavior::OnFailure => "on-failure",
+        RestartBehavior::Never => "no",
+    }
+}
+
+fn service(job: &Job, targets: bool) -> String {
+    let mut unit = String::new();
+    let _ = writeln!(unit, "[Unit]");
+    let _ = writeln!(unit, "Description=tend job {} ({})", job.name, job.group);
+    for dependency in &job.depends_on {
+        let dependency = unit_name(dependency, "service");
+        let _ = writeln!(unit, "Requires={dependency}");
+        let _ = writeln!(unit, "After={dependency}");
+    }
+    if targets {
+        let _ = writeln!(unit, "PartOf={}", unit_name(&job.group, "target"));
+    }
+    match job.max_restarts {
+        // Restarts within ten minutes count towards the limit, as in tend.
+        Some(max_restarts) => {
+            let _ = writeln!(unit, "StartLimitIntervalSec=600");
+            let _ = writeln!(unit, "StartLimitBurst={}", max_restarts + 1);
+        }
+        None => {
+            let _ = writeln!(unit, "StartLimitIntervalSec=0");
+        }
+    }
+
+    let _ = writeln!(unit, "\n[Service]");
+    let _ = writeln!(unit, "Type=simple");
+    let _ = writeln!(
+        unit,
+        "WorkingDirectory={}",
+        job.working_directory.to_string_lossy().replace('%', "%%")
+    );
+    let mut exec_start = quote(&resolve_program(job).to_string_lossy(), true);
+    for arg in &job.args {
+        exec_start.push(' ');
+        exec_start.push_str(&quote(arg, true));
+    }
+    let _ = writeln!(unit, "ExecStart={exec_start}");
+    if let Some(env_file) = &job.env_file {
+        let env_file = job.working_directory.join(env_file);
+        let _ = writeln!(
+            unit,
+            "EnvironmentFile=-{}",
+            env_file.to_string_lossy().replace('%', "%%")
+        );
+    }
+    for (key, value) in &job.env {
+        let _ = writeln!(
+            unit,
+            "Environment={}",
+            quote(&format!("{key}={value}"), false)
+        );
+    }
+    let _ = writeln!(unit, "Restart={}", restart(job.restart));
+    match job.restart_strategy {
+        RestartStrategy::Immediate => {
+            let _ = writeln!(unit, "RestartSec=0");
+        }
+        RestartStrategy::ExponentialBackoff => {
+            // Closest match to tend's delays of up to a minute, needs systemd 254 or later.
+            let _ = writeln!(unit, "RestartSec=1");
+            let _ = writeln!(unit, "RestartSteps=6");
+            let _ = writeln!(unit, "RestartMaxDelaySec=60");
+        }
+    }
+    let _ = writeln!(unit, "KillSignal={}", job.stop_signal);
+    let _ = writeln!(unit, "TimeoutStopSec={}", job.stop_timeout_seconds);
+
+    // Disabled jobs only start as dependencies of other jobs, or by hand.
+    if job.enabled {
+        let _ = writeln!(unit, "\n[Install]");
+        if targets {
+            let _ = writeln!(unit, "WantedBy={}", unit_name(&job.group, "target"));
+        } else {
+            let _ = writeln!(unit, "WantedBy=default.target");
+        }
+    }
+    unit
+}
+
+fn target(group: &str, jobs: &[&Job]) -> String {
+    let mut unit = String::new();
+    let _ = writeln!(unit, "[Unit]");
+    let _ = writeln!(unit, "Description=tend group {group}");
+    let wants: Vec<String> = jobs
+        .iter()
+        .filter(|job| job.enabled)
+        .map(|job| unit_name(&job.name, "service"))
+        .collect();
+    let _ = writeln!(unit, "Wants={}", wants.join(" "));
+    let _ = writeln!(unit, "\n[Install]");
+    let _ = writeln!(unit, "WantedBy=default.target");
+    unit
+}
+
+/// Warns about job settings that have no systemd equivalent.
+fn warn_unsupported(job: &Job) {
+    let mut unsupported = vec![];
+    if !job.event_hooks.is_empty() {
+        unsupported.push("hooks");
+    }
+    if job.readiness.is_some() {
+        unsupported.push("readiness probes");
+    }
+    if !job.liveness.is_empty() {
+        unsupported.push("liveness checks");
+    }
+    if job.clear_env {
+        unsupported.push("environment clearing");
+    }
+    if job.log.is_some() {
+        unsupported.push("log files (use journalctl)");
+    }
+    if !unsupported.is_empty() {
+        eprintln!(
+            "{} {} {}",
+            job.name.job(),
+            "has settings systemd units cannot express:".failure(),
+            unsupported.join(", ")
+        );
+    }
+}
+
+/// Warns about dependencies on jobs that are not exported, as their units will fail to start.
+fn warn_missing_dependencies(job: &Job, jobs: &[Job]) {
+    for dependency in &job.depends_on {
+        if !jobs.iter().any(|job| job.name == *dependency) {
+            eprintln!(
+                "{} {} {}",
+                job.name.job(),
+                "depends on a job that is not exported:".failure(),
+                dependency.job()
+            );
+        }
+    }
+}
+
+/// Fails if `unit` was already generated for something else than `name`, since one unit would
+/// overwrite the other.
+fn check_collision<'a>(
+    names: &mut BTreeMap<String, &'a str>,
+    unit: &str,
+    name: &'a str,
+) -> Result<()> {
+    if let Some(other) = names.insert(unit.to_string(), name) {
+        anyhow::bail!("{other} and {name} would both be exported as {unit}.");
+    }
+    Ok(())
+}
+
+pub fn export(filter: &Filter, options: &SystemdOptions, verbose: bool) -> Result<()> {
+    let mut jobs = vec![];
+    Job::iterate_jobs_filtered(|job| jobs.push(job), filter, true, verbose)?;
+    if jobs.is_empty() {
+        anyhow::bail!("No jobs matched.");
+    }
+
+    let mut units = vec![];
+    let mut names = BTreeMap::new();
+    for job in &jobs {
+        warn_unsupported(job);
+        warn_missing_dependencies(job, &jobs);
+        let name = unit_name(&job.name, "service");
+        check_collision(&mut names, &name, &job.name)?;
+        units.push((name, service(job, options.targets)));
+    }
+    if options.targets {
+        let mut groups: BTreeMap<&str, Vec<&Job>> = BTreeMap::new();
+        for job in &jobs {
+            groups.entry(&job.group).or_default().push(job);
+        }
+        for (group, jobs) in groups {
+            if jobs.iter().all(|job| !job.enabled) {
+                continue;
+            }
+            let name = unit_name(group, "target");
+            check_collision(&mut names, &name, group)?;
+            units.push((name, target(group, &jobs)));
+        }
+    }
+
+    match &options.output {
+        Some(output) => {
+            std::fs::create_dir_all(output)?;
+            for (name, unit) in &units {
+                std::fs::write(output.join(name), unit)?;
+                println!("{} written", output.join(name).display());
+            }
+            println!("Run `systemctl --user daemon-reload` to load the units.");
+        }
+        None => {
+            for (index, (name, unit)) in units.iter().enumerate() {
+                if index > 0 {
+                    println!();
+                }
+                println!("# {name}");
+                print!("{unit}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_collision, quote, service, unit_name};
+    use crate::job::Job;
+    use std::{collections::BTreeMap, path::PathBuf};
+
+    fn job(name: &str, program: &str, args: &[&str]) -> Job {
+        Job::new(
+            name.to_string(),
+            program.to_string(),
+            args.iter().map(ToString::to_string).collect(),
+            PathBuf::from("/srv/app"),
+        )
+    }
+
+    #[test]
+    fn unit_names_replace_unsupported_characters() {
+        assert_eq!(unit_name("web", "service"), "tend-web.service");
+        assert_eq!(
+            unit_name("proj/api v2", "service"),
+            "tend-proj-api-v2.service"
+        );
+        assert_eq!(unit_name("a:b_c.d", "target"), "tend-a:b_c.d.target");
+    }
+
+    #[test]
+    fn colliding_unit_names_are_rejected() {
+        let mut names = BTreeMap::new();
+        check_collision(&mut names, &unit_name("proj/api", "service"), "proj/api").unwrap();
+        let error =
+            check_collision(&mut names, &unit_name("proj-api", "service"), "proj-api").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "proj/api and proj-api would both be exported as tend-proj-api.service."
+        );
+    }
+
+    #[test]
+    fn quote_escapes_systemd_specifiers() {
+        assert_eq!(quote("plain", true), "plain");
+        assert_eq!(quote("", true), "\"\"");
+        assert_eq!(quote("two words", true), "\"two words\"");
+        assert_eq!(quote("say \"hi\"", true), "\"say \\\"hi\\\"\"");
+        assert_eq!(quote("a\\b", true), "\"a\\\\b\"");
+        assert_eq!(quote("100%", true), "\"100%%\"");
+        assert_eq!(quote("$HOME", true), "\"$$HOME\"");
+        assert_eq!(quote("KEY=$HOME", false), "KEY=$HOME");
+    }
+
+    #[test]
+    fn service_escapes_arguments_and_environment() {
+        let mut job = job("web", "/bin/echo", &["$USER", "50%"]);
+        job.env
+            .insert("GREETING".to_string(), "hello world".to_string());
+        let unit = service(&job, false);
+        assert!(unit.contains("ExecStart=/bin/echo \"$$USER\" \"50%%\"\n"));
+        assert!(unit.contains("Environment=\"GREETING=hello world\"\n"));
+        assert!(unit.contains("WorkingDirectory=/srv/app\n"));
+        assert!(unit.contains("WantedBy=default.target\n"));
+    }
+
+    #[test]
+    fn service_lists_dependencies_and_targets() {
+        let mut job = job("proj/api", "/bin/true", &[]);
+        job.group = "proj".to_string();
+        job.depends_on = vec!["proj/db".to_string()];
+        let unit = service(&job, true);
+        assert!(unit.contains("Requires=tend-proj-db.service\nAfter=tend-proj-db.service\n"));
+        assert!(unit.contains("PartOf=tend-proj.target\n"));
+        assert!(unit.contains("WantedBy=tend-proj.target\n"));
+    }
+
+    #[test]
+    fn disabled_jobs_are_not_installed() {
+        let mut job = job("web", "/bin/true", &[]);
+        job.enabled = false;
+        assert!(!service(&job, false).contains("[Install]"));
+        assert!(!service(&job, true).contains("WantedBy="));
+    }
+}